@@ -0,0 +1,174 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use image::io::Reader as ImageReader;
+use image::RgbImage;
+
+use rsc::array::positions_from_image;
+use rsc::{energy, seam};
+
+const SIZES: [(u32, u32); 3] = [(64, 64), (256, 256), (1024, 768)];
+const SEAMS: u32 = 10;
+
+// the gradient image of the unit tests, the other helpers being unused here
+#[allow(dead_code)]
+#[path = "../src/test_utils.rs"]
+mod test_utils;
+
+fn images() -> Vec<(String, RgbImage)> {
+    let mut images: Vec<_> = SIZES
+        .iter()
+        .map(|&(width, height)| {
+            (
+                format!("synthetic_{}x{}", width, height),
+                test_utils::gradient_img(width, height),
+            )
+        })
+        .collect();
+    let broadway = ImageReader::open("./img/Broadway_tower_edit.jpg")
+        .unwrap()
+        .decode()
+        .unwrap()
+        .to_rgb8();
+    images.push((String::from("broadway_tower"), broadway));
+    images
+}
+
+fn bench_energy(c: &mut Criterion) {
+    let mut group = c.benchmark_group("energy");
+    for (name, img) in images() {
+        let positions = positions_from_image(&img).unwrap();
+        group.bench_with_input(BenchmarkId::new("get_energy_img", &name), &img, |b, img| {
+            b.iter(|| energy::get_energy_img(img, &positions).unwrap())
+        });
+
+        let energy_map = energy::get_energy_img(&img, &positions).unwrap();
+        let seam = seam::find_vertical_seam(&energy_map);
+        let mut positions_carved = positions.clone();
+        positions_carved.remove_seam(&seam).unwrap();
+        group.bench_with_input(
+            BenchmarkId::new("update_energy_img", &name),
+            &img,
+            |b, img| {
+                b.iter_batched_ref(
+                    || energy_map.clone(),
+                    |energy_map| {
+                        energy::update_energy_img(energy_map, img, &positions_carved, &seam)
+                            .unwrap()
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_seam(c: &mut Criterion) {
+    let mut group = c.benchmark_group("seam");
+    for (name, img) in images() {
+        let positions = positions_from_image(&img).unwrap();
+        let energy_map = energy::get_energy_img(&img, &positions).unwrap();
+        group.bench_with_input(
+            BenchmarkId::new("find_vertical_seam", &name),
+            &energy_map,
+            |b, energy_map| b.iter(|| seam::find_vertical_seam(energy_map)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("find_horizontal_seam", &name),
+            &energy_map,
+            |b, energy_map| b.iter(|| seam::find_horizontal_seam(energy_map)),
+        );
+
+        let vertical_seam = seam::find_vertical_seam(&energy_map);
+        group.bench_with_input(
+            BenchmarkId::new("remove_seam", &name),
+            &positions,
+            |b, positions| {
+                b.iter_batched_ref(
+                    || positions.clone(),
+                    |positions| positions.remove_seam(&vertical_seam).unwrap(),
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+        // the same disjoint seams, removed in one compaction pass or one after the other
+        let seams = seam::find_vertical_seams(&energy_map, SEAMS as usize);
+        let sequential: Vec<Vec<usize>> = seams
+            .iter()
+            .enumerate()
+            .map(|(k, seam)| {
+                seam.iter()
+                    .enumerate()
+                    .map(|(y, &x)| x - seams[..k].iter().filter(|seam| seam[y] < x).count())
+                    .collect()
+            })
+            .collect();
+        group.bench_with_input(
+            BenchmarkId::new("remove_seams", &name),
+            &positions,
+            |b, positions| {
+                b.iter_batched_ref(
+                    || positions.clone(),
+                    |positions| positions.remove_seams(&seams).unwrap(),
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("remove_seam_sequential", &name),
+            &positions,
+            |b, positions| {
+                b.iter_batched_ref(
+                    || positions.clone(),
+                    |positions| {
+                        for seam in &sequential {
+                            positions.remove_seam(seam).unwrap();
+                        }
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+        let horizontal_seam = seam::find_horizontal_seam(&energy_map);
+        group.bench_with_input(
+            BenchmarkId::new("remove_horizontal_seam", &name),
+            &positions,
+            |b, positions| {
+                b.iter_batched_ref(
+                    || positions.clone(),
+                    |positions| positions.remove_horizontal_seam(&horizontal_seam).unwrap(),
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("transpose", &name),
+            &positions,
+            |b, positions| {
+                b.iter_batched_ref(
+                    || positions.clone(),
+                    |positions| positions.transpose(),
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_seamcarve(c: &mut Criterion) {
+    let mut group = c.benchmark_group("seamcarve");
+    group.sample_size(10);
+    for (name, img) in images() {
+        let (width, height) = img.dimensions();
+        group.bench_with_input(BenchmarkId::new("carve", &name), &img, |b, img| {
+            b.iter(|| rsc::seamcarve(img, width - SEAMS, height - SEAMS).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("insert", &name), &img, |b, img| {
+            b.iter(|| rsc::seamcarve(img, width + SEAMS, height + SEAMS).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_energy, bench_seam, bench_seamcarve);
+criterion_main!(benches);
@@ -0,0 +1,108 @@
+#ifndef RSC_H
+#define RSC_H
+
+/* generated with `cbindgen --config cbindgen.toml --output include/rsc.h src/ffi.rs`, do not edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define RSC_ENERGY_DUAL_GRADIENT 0
+
+#define RSC_ENERGY_GRADIENT 1
+
+#define RSC_BORDER_WRAP 0
+
+#define RSC_BORDER_CLAMP 1
+
+#define RSC_ORDER_VERTICAL_FIRST 0
+
+#define RSC_ORDER_HORIZONTAL_FIRST 1
+
+#define RSC_ORDER_INTERLEAVED 2
+
+typedef enum RscStatus {
+  RSC_STATUS_OK = 0,
+  RSC_STATUS_NULL_POINTER = 1,
+  RSC_STATUS_INVALID_STRIDE = 2,
+  RSC_STATUS_INVALID_DIMENSIONS = 3,
+  RSC_STATUS_INVALID_OPTION = 4,
+  RSC_STATUS_CARVING_FAILED = 5,
+  RSC_STATUS_PANICKED = 6,
+} RscStatus;
+
+/**
+ * Carving options, enums being passed as integers so that values unknown to this version of
+ * the library are reported instead of being undefined behaviour.
+ */
+typedef struct RscOptions {
+  uint32_t energy;
+  uint32_t border;
+  uint32_t order;
+  uint32_t threads;
+  uint32_t seams_per_pass;
+  /**
+   * width * height bytes, non-zero pixels being protected from carving; may be null
+   */
+  const uint8_t *protect;
+} RscOptions;
+
+/**
+ * Rgba pixels owned by the library, to be released with rsc_image_free.
+ */
+typedef struct RscImage {
+  uint8_t *data;
+  size_t len;
+  uint32_t width;
+  uint32_t height;
+  uint32_t stride;
+} RscImage;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Options matching the defaults of the Rust API.
+ */
+struct RscOptions rsc_default_options(void);
+
+/**
+ * Carves rows of `stride` bytes holding `width` rgba pixels each, with the default options
+ * when `options` is null.
+ *
+ * # Safety
+ *
+ * `rgba` must point to `height` rows of `stride` bytes, `options` to valid options (whose
+ * mask, if any, holds `width * height` bytes) or be null, and `out` to writable memory.
+ */
+enum RscStatus rsc_carve(const uint8_t *rgba,
+                         uint32_t width,
+                         uint32_t height,
+                         uint32_t stride,
+                         uint32_t new_width,
+                         uint32_t new_height,
+                         const struct RscOptions *options,
+                         struct RscImage *out);
+
+/**
+ * Releases the pixels of a carved image, leaving it empty.
+ *
+ * # Safety
+ *
+ * `image` must be null or have been filled by rsc_carve, and not have been freed yet.
+ */
+void rsc_image_free(struct RscImage *image);
+
+/**
+ * Message of the last error on the calling thread, valid until the next call failing on it.
+ */
+const char *rsc_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* RSC_H */
@@ -1,10 +1,21 @@
-use std::error::Error;
-use std::fmt;
-use std::ops::{Index, IndexMut};
-
-use image::{Rgb, RgbImage};
-
-#[derive(Eq, PartialEq)]
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+use core::iter::{self, Skip, StepBy};
+use core::ops::{Index, IndexMut};
+use core::slice::{self, ChunksExact, ChunksExactMut};
+
+#[cfg(feature = "image")]
+use image::{GrayImage, ImageBuffer, Pixel, Rgb, RgbImage};
+#[cfg(feature = "ndarray")]
+use ndarray::{Array2, ArrayView2};
+
+const TRANSPOSE_BLOCK: usize = 32;
+
+#[derive(Clone, Eq, PartialEq)]
 pub struct Array2d<T> {
     width: usize,
     data: Vec<T>,
@@ -72,15 +83,163 @@ impl<T> Array2d<T> {
     pub fn raw_data_mut(&mut self) -> &mut [T] {
         &mut self.data
     }
+
+    pub fn into_raw_data(self) -> Vec<T> {
+        self.data
+    }
+
+    pub fn rows(&self) -> ChunksExact<'_, T> {
+        self.data.chunks_exact(self.width)
+    }
+
+    pub fn rows_mut(&mut self) -> ChunksExactMut<'_, T> {
+        self.data.chunks_exact_mut(self.width)
+    }
+
+    pub fn iter_indexed(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        let width = self.width;
+        self.data
+            .iter()
+            .enumerate()
+            .map(move |(i, value)| ((i % width, i / width), value))
+    }
+
+    pub fn iter_indexed_mut(&mut self) -> impl Iterator<Item = ((usize, usize), &mut T)> {
+        let width = self.width;
+        self.data
+            .iter_mut()
+            .enumerate()
+            .map(move |(i, value)| ((i % width, i / width), value))
+    }
+
+    pub fn column(&self, x: usize) -> Result<StepBy<Skip<slice::Iter<'_, T>>>, Box<dyn Error>> {
+        if x >= self.width {
+            return Err(format!("column index out of bounds, got {} and {}", x, self.width).into());
+        }
+        Ok(self.data.iter().skip(x).step_by(self.width))
+    }
+
+    pub fn view(
+        &self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> Result<ArrayView<'_, T>, Box<dyn Error>> {
+        let fits = |start: usize, len: usize, size: usize| {
+            start.checked_add(len).is_some_and(|end| end <= size)
+        };
+        if !fits(x, width, self.width) || !fits(y, height, self.height()) {
+            return Err(format!(
+                "view out of bounds, got {}x{} at ({}, {}) and {}x{}",
+                width,
+                height,
+                x,
+                y,
+                self.width,
+                self.height()
+            )
+            .into());
+        }
+        Ok(ArrayView {
+            array: self,
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct ArrayView<'a, T> {
+    array: &'a Array2d<T>,
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArrayView<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "ArrayView {{")?;
+        for row in self.rows() {
+            writeln!(f, "{:?}", row)?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+impl<T> Index<(usize, usize)> for ArrayView<'_, T> {
+    type Output = T;
+
+    fn index(&self, index: (usize, usize)) -> &Self::Output {
+        let (x, y) = index;
+        assert!(
+            x < self.width && y < self.height,
+            "view index out of bounds, got ({}, {}) and {}x{}",
+            x,
+            y,
+            self.width,
+            self.height
+        );
+        &self.array[(self.x + x, self.y + y)]
+    }
+}
+
+impl<'a, T> ArrayView<'a, T> {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &'a [T]> {
+        let (x, width) = (self.x, self.width);
+        self.array
+            .rows()
+            .skip(self.y)
+            .take(self.height)
+            .map(move |row| &row[x..x + width])
+    }
+
+    pub fn iter_indexed(&self) -> impl Iterator<Item = ((usize, usize), &'a T)> {
+        self.rows().enumerate().flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .map(move |(x, value)| ((x, y), value))
+        })
+    }
+}
+
+impl<T: Copy> ArrayView<'_, T> {
+    pub fn to_array(&self) -> Array2d<T> {
+        let mut data = Vec::with_capacity(self.width * self.height);
+        self.rows().for_each(|row| data.extend_from_slice(row));
+        Array2d {
+            width: self.width,
+            data,
+        }
+    }
 }
 
 impl<T: Copy> Array2d<T> {
     pub fn transpose(&mut self) {
         let (width, height) = self.dimensions();
-        let mut new_data = Vec::with_capacity(self.size());
-        for x in 0..width {
-            for y in 0..height {
-                new_data.push(self[(x, y)])
+        let mut new_data = self.data.clone(); // every element is overwritten below
+        for y_block in (0..height).step_by(TRANSPOSE_BLOCK) {
+            for x_block in (0..width).step_by(TRANSPOSE_BLOCK) {
+                for y in y_block..height.min(y_block + TRANSPOSE_BLOCK) {
+                    for x in x_block..width.min(x_block + TRANSPOSE_BLOCK) {
+                        new_data[y + x * height] = self.data[x + y * width];
+                    }
+                }
             }
         }
         self.width = height;
@@ -97,22 +256,181 @@ impl<T: Copy> Array2d<T> {
             )
             .into());
         }
+        if let Some(&x) = seam.iter().find(|&&x| x >= width) {
+            return Err(format!("seam index out of bounds, got {} and {}", x, width).into());
+        }
+
+        self.compact(seam, 1);
+        Ok(())
+    }
+
+    pub fn remove_horizontal_seam(&mut self, seam: &[usize]) -> Result<(), Box<dyn Error>> {
+        let (width, height) = self.dimensions();
+        if seam.len() != width {
+            return Err(format!(
+                "seam length and image width should be equal, got {} and {}",
+                seam.len(),
+                width
+            )
+            .into());
+        }
+        if let Some(&y) = seam.iter().find(|&&y| y >= height) {
+            return Err(format!("seam index out of bounds, got {} and {}", y, height).into());
+        }
+
+        for y in 0..(height - 1) {
+            for (x, &to_remove_y) in seam.iter().enumerate() {
+                if y >= to_remove_y {
+                    self.data[x + y * width] = self.data[x + (y + 1) * width];
+                }
+            }
+        }
+        self.data.truncate(width * (height - 1));
+        Ok(())
+    }
+
+    pub fn remove_seams(&mut self, seams: &[Vec<usize>]) -> Result<(), Box<dyn Error>> {
+        let (width, height) = self.dimensions();
+        let to_remove = seams.len();
+        if to_remove >= width {
+            return Err(format!(
+                "number of seams should be smaller than width, got {} and {}",
+                to_remove, width
+            )
+            .into());
+        }
+        if let Some(seam) = seams.iter().find(|seam| seam.len() != height) {
+            return Err(format!(
+                "seam length and image height should be equal, got {} and {}",
+                seam.len(),
+                height
+            )
+            .into());
+        }
+
+        let mut columns = Vec::with_capacity(to_remove * height);
+        for y in 0..height {
+            columns.extend(seams.iter().map(|seam| seam[y]));
+            let row = &mut columns[y * to_remove..];
+            row.sort_unstable();
+            if let Some(&x) = row.last().filter(|&&x| x >= width) {
+                return Err(format!("seam index out of bounds, got {} and {}", x, width).into());
+            }
+            if let Some(pair) = row.windows(2).find(|pair| pair[0] == pair[1]) {
+                return Err(format!(
+                    "seams should not overlap, got column {} twice in row {}",
+                    pair[0], y
+                )
+                .into());
+            }
+        }
+
+        self.compact(&columns, to_remove);
+        Ok(())
+    }
 
-        let mut new_data = Vec::with_capacity(self.size() - height);
-        seam.iter().enumerate().for_each(|(y, &to_remove_x)| {
+    // columns holds the sorted indices to remove from each row, to_remove per row
+    // rows only move up within their column, so each element is written over one already read
+    pub fn remove_horizontal_seams(&mut self, seams: &[Vec<usize>]) -> Result<(), Box<dyn Error>> {
+        let (width, height) = self.dimensions();
+        let to_remove = seams.len();
+        if to_remove >= height {
+            return Err(format!(
+                "number of seams should be smaller than height, got {} and {}",
+                to_remove, height
+            )
+            .into());
+        }
+        if let Some(seam) = seams.iter().find(|seam| seam.len() != width) {
+            return Err(format!(
+                "seam length and image width should be equal, got {} and {}",
+                seam.len(),
+                width
+            )
+            .into());
+        }
+
+        let mut rows = Vec::with_capacity(to_remove * width);
+        for x in 0..width {
+            rows.extend(seams.iter().map(|seam| seam[x]));
+            let column = &mut rows[x * to_remove..];
+            column.sort_unstable();
+            if let Some(&y) = column.last().filter(|&&y| y >= height) {
+                return Err(format!("seam index out of bounds, got {} and {}", y, height).into());
+            }
+            if let Some(pair) = column.windows(2).find(|pair| pair[0] == pair[1]) {
+                return Err(format!(
+                    "seams should not overlap, got row {} twice in column {}",
+                    pair[0], x
+                )
+                .into());
+            }
+        }
+
+        let mut removed = vec![0; width];
+        for y in 0..height {
             for x in 0..width {
-                if x != to_remove_x {
-                    new_data.push(self[(x, y)]);
+                let column = &rows[x * to_remove..(x + 1) * to_remove];
+                if column.get(removed[x]) == Some(&y) {
+                    removed[x] += 1;
+                } else {
+                    self.data[x + (y - removed[x]) * width] = self.data[x + y * width];
                 }
             }
+        }
+        self.data.truncate(width * (height - to_remove));
+        Ok(())
+    }
+
+    fn compact(&mut self, columns: &[usize], to_remove: usize) {
+        let (width, height) = self.dimensions();
+        let mut write = 0;
+        for y in 0..height {
+            let row = y * width;
+            let mut start = 0;
+            let removed = &columns[y * to_remove..(y + 1) * to_remove];
+            for &x in removed.iter().chain(iter::once(&width)) {
+                self.data.copy_within(row + start..row + x, write);
+                write += x - start;
+                start = x + 1;
+            }
+        }
+        self.data.truncate(write);
+        self.width -= to_remove;
+    }
+
+    pub fn insert_seam(&mut self, seam: &[usize], values: &[T]) -> Result<(), Box<dyn Error>> {
+        let (width, height) = self.dimensions();
+        if (seam.len() != height) || (values.len() != height) {
+            return Err(format!(
+                "seam length, values length and image height should be equal, got {}, {} and {}",
+                seam.len(),
+                values.len(),
+                height
+            )
+            .into());
+        }
+
+        let mut new_data = Vec::with_capacity(self.size() + height);
+        seam.iter().enumerate().for_each(|(y, &to_insert_x)| {
+            for x in 0..width {
+                if x == to_insert_x {
+                    new_data.push(values[y]);
+                }
+                new_data.push(self[(x, y)]);
+            }
+            if to_insert_x >= width {
+                new_data.push(values[y]);
+            }
         });
 
         self.data = new_data;
-        self.width -= 1;
+        self.width += 1;
         Ok(())
     }
 }
 
+#[cfg(feature = "image")]
 impl Array2d<Rgb<u8>> {
     pub fn from_image(img: &RgbImage) -> Result<Self, Box<dyn Error>> {
         let (width, height) = img.dimensions();
@@ -124,17 +442,73 @@ impl Array2d<Rgb<u8>> {
     pub fn to_image(&self) -> RgbImage {
         let (width, height) = self.dimensions();
         let mut img = RgbImage::new(width as u32, height as u32);
-        for (i, &p) in self.data.iter().enumerate() {
-            // TODO: implement iterator
-            let (x, y) = (i % width, i / width);
+        for ((x, y), &p) in self.iter_indexed() {
             img.put_pixel(x as u32, y as u32, p);
         }
         img
     }
 }
 
-pub fn positions_from_image(img: &RgbImage) -> Result<Array2d<(u32, u32)>, Box<dyn Error>> {
-    let (width, height) = img.dimensions();
+#[cfg(feature = "image")]
+impl Array2d<bool> {
+    pub fn from_mask(mask: &GrayImage) -> Result<Self, Box<dyn Error>> {
+        let (width, height) = mask.dimensions();
+        let mut data = Vec::with_capacity((width * height) as usize);
+        mask.pixels().for_each(|p| data.push(p.0[0] > 0));
+        Self::new(width as usize, data)
+    }
+}
+
+// ndarray arrays are indexed by (row, col), i.e. have shape (height, width)
+#[cfg(feature = "ndarray")]
+impl<T> Array2d<T> {
+    // standard layout arrays are moved without copying their elements
+    pub fn from_ndarray(array: Array2<T>) -> Result<Self, Box<dyn Error>>
+    where
+        T: Clone,
+    {
+        let (height, width) = array.dim();
+        if width == 0 {
+            return Err(format!("arrays must not be empty, got {}x{}", width, height).into());
+        }
+        if !array.is_standard_layout() {
+            return Self::new(width, array.iter().cloned().collect());
+        }
+        // sliced arrays may keep elements out of their view in their buffer
+        let len = array.len();
+        let (mut data, offset) = array.into_raw_vec_and_offset();
+        let offset = offset.unwrap_or(0);
+        data.truncate(offset + len);
+        data.drain(..offset);
+        Self::new(width, data)
+    }
+
+    pub fn from_ndarray_view(view: ArrayView2<'_, T>) -> Result<Self, Box<dyn Error>>
+    where
+        T: Clone,
+    {
+        let (height, width) = view.dim();
+        if width == 0 {
+            return Err(format!("arrays must not be empty, got {}x{}", width, height).into());
+        }
+        Self::new(width, view.iter().cloned().collect())
+    }
+
+    pub fn into_ndarray(self) -> Array2<T> {
+        let (width, height) = self.dimensions();
+        Array2::from_shape_vec((height, width), self.data).unwrap()
+    }
+
+    pub fn as_ndarray(&self) -> ArrayView2<'_, T> {
+        let (width, height) = self.dimensions();
+        ArrayView2::from_shape((height, width), &self.data).unwrap()
+    }
+}
+
+pub fn positions_from_dimensions(
+    width: u32,
+    height: u32,
+) -> Result<Array2d<(u32, u32)>, Box<dyn Error>> {
     let mut data = Vec::with_capacity((width * height) as usize);
     for y in 0..height {
         for x in 0..width {
@@ -144,17 +518,40 @@ pub fn positions_from_image(img: &RgbImage) -> Result<Array2d<(u32, u32)>, Box<d
     Array2d::new(width as usize, data)
 }
 
-pub fn filter_image_by_positions(img: &RgbImage, positions: &Array2d<(u32, u32)>) -> RgbImage {
+#[cfg(feature = "image")]
+pub fn positions_from_image(img: &RgbImage) -> Result<Array2d<(u32, u32)>, Box<dyn Error>> {
+    let (width, height) = img.dimensions();
+    positions_from_dimensions(width, height)
+}
+
+#[cfg(feature = "image")]
+pub fn filter_image_by_positions<P: Pixel>(
+    img: &ImageBuffer<P, Vec<P::Subpixel>>,
+    positions: &Array2d<(u32, u32)>,
+) -> ImageBuffer<P, Vec<P::Subpixel>> {
     let (width, height) = positions.dimensions();
-    let mut new_img = RgbImage::new(width as u32, height as u32);
-    for x in 0..width {
-        for y in 0..height {
-            new_img.put_pixel(x as u32, y as u32, img[positions[(x, y)]])
-        }
+    let mut new_img = ImageBuffer::new(width as u32, height as u32);
+    for ((x, y), &position) in positions.iter_indexed() {
+        new_img.put_pixel(x as u32, y as u32, img[position])
     }
     new_img
 }
 
+pub fn filter_array_by_positions<T: Copy>(
+    arr: &Array2d<T>,
+    positions: &Array2d<(u32, u32)>,
+) -> Array2d<T> {
+    let data = positions
+        .raw_data()
+        .iter()
+        .map(|&(px, py)| arr[(px as usize, py as usize)])
+        .collect();
+    Array2d {
+        width: positions.width(),
+        data,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -214,6 +611,85 @@ mod tests {
         let mut arr = Array2d::new(3, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]).unwrap();
         arr.raw_data_mut()[0] = 3;
         assert_eq!(3, arr.raw_data_mut()[0]);
+        assert_eq!(vec![3, 2, 3, 4, 5, 6, 7, 8, 9], arr.into_raw_data());
+    }
+
+    #[test]
+    fn rows() {
+        let mut arr = Array2d::new(3, vec![1, 2, 3, 4, 5, 6]).unwrap();
+        assert_eq!(vec![&[1, 2, 3], &[4, 5, 6]], arr.rows().collect::<Vec<_>>());
+        arr.rows_mut().for_each(|row| row.reverse());
+        assert_eq!(Array2d::new(3, vec![3, 2, 1, 6, 5, 4]).unwrap(), arr);
+    }
+
+    #[test]
+    fn iteration_indexed() {
+        let mut arr = Array2d::new(2, vec![1, 2, 3, 4]).unwrap();
+        assert_eq!(
+            vec![((0, 0), &1), ((1, 0), &2), ((0, 1), &3), ((1, 1), &4)],
+            arr.iter_indexed().collect::<Vec<_>>()
+        );
+        arr.iter_indexed_mut()
+            .for_each(|((x, y), v)| *v += 10 * x + 100 * y);
+        assert_eq!(Array2d::new(2, vec![1, 12, 103, 114]).unwrap(), arr);
+    }
+
+    #[test]
+    fn columns() {
+        let arr = Array2d::new(3, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]).unwrap();
+        assert_eq!(vec![&2, &5, &8], arr.column(1).unwrap().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn columns_out_of_bounds() {
+        let arr = Array2d::new(3, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]).unwrap();
+        assert_eq!(
+            Err(String::from("column index out of bounds, got 3 and 3")),
+            arr.column(3).map(|_| ()).map_err(|e| format!("{}", e))
+        );
+    }
+
+    #[test]
+    fn views() {
+        let arr = Array2d::new(4, (0..16).collect()).unwrap();
+        let view = arr.view(1, 2, 2, 2).unwrap();
+        assert_eq!((2, 2), view.dimensions());
+        assert_eq!(10, view[(1, 0)]);
+        assert_eq!(vec![&[9, 10], &[13, 14]], view.rows().collect::<Vec<_>>());
+        assert_eq!(
+            vec![((0, 0), &9), ((1, 0), &10), ((0, 1), &13), ((1, 1), &14)],
+            view.iter_indexed().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Array2d::new(2, vec![9, 10, 13, 14]).unwrap(),
+            view.to_array()
+        );
+        assert_eq!(
+            String::from("ArrayView {\n[9, 10]\n[13, 14]\n}\n"),
+            format!("{:?}", view)
+        );
+    }
+
+    #[test]
+    fn views_out_of_bounds() {
+        let arr = Array2d::new(4, (0..16).collect()).unwrap();
+        assert_eq!(
+            Err(String::from(
+                "view out of bounds, got 2x3 at (3, 2) and 4x4"
+            )),
+            arr.view(3, 2, 2, 3)
+                .map(|v| v.to_array())
+                .map_err(|e| format!("{}", e))
+        );
+        assert_eq!(
+            Err(format!(
+                "view out of bounds, got 2x{} at (1, 1) and 4x4",
+                usize::MAX
+            )),
+            arr.view(1, 1, 2, usize::MAX)
+                .map(|v| v.to_array())
+                .map_err(|e| format!("{}", e))
+        );
     }
 
     #[test]
@@ -244,6 +720,127 @@ mod tests {
         assert_eq!(Array2d::new(3, vec![1, 2, 3, 7, 5, 9]).unwrap(), arr);
     }
 
+    #[test]
+    fn seam_removal_out_of_bounds() {
+        let mut arr = Array2d::new(3, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]).unwrap();
+        assert_eq!(
+            Err(String::from("seam index out of bounds, got 3 and 3")),
+            arr.remove_seam(&[1, 3, 1]).map_err(|e| format!("{}", e))
+        );
+        assert_eq!(
+            Array2d::new(3, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]).unwrap(),
+            arr
+        );
+    }
+
+    #[test]
+    fn seams_removal() {
+        let mut arr = Array2d::new(4, (0..12).collect()).unwrap();
+        let seams = vec![vec![3, 2, 3], vec![0, 1, 1]];
+        arr.remove_seams(&seams).unwrap();
+        assert_eq!(Array2d::new(2, vec![1, 2, 4, 7, 8, 10]).unwrap(), arr);
+    }
+
+    #[test]
+    fn seams_removal_sequential() {
+        let mut arr = Array2d::new(5, (0..20).collect()).unwrap();
+        let mut arr_sequential = arr.clone();
+        arr.remove_seams(&[vec![4, 3, 2, 1], vec![0, 1, 1, 0]])
+            .unwrap();
+        arr_sequential.remove_seam(&[4, 3, 2, 1]).unwrap();
+        arr_sequential.remove_seam(&[0, 1, 1, 0]).unwrap();
+        assert_eq!(arr_sequential, arr);
+    }
+
+    #[test]
+    fn seams_overlapping() {
+        let mut arr = Array2d::new(4, (0..12).collect()).unwrap();
+        let seams = vec![vec![3, 2, 3], vec![0, 2, 1]];
+        assert_eq!(
+            Err(String::from(
+                "seams should not overlap, got column 2 twice in row 1"
+            )),
+            arr.remove_seams(&seams).map_err(|e| format!("{}", e))
+        );
+        assert_eq!(Array2d::new(4, (0..12).collect()).unwrap(), arr);
+    }
+
+    #[test]
+    fn seams_too_many() {
+        let mut arr = Array2d::new(2, (0..4).collect()).unwrap();
+        assert_eq!(
+            Err(String::from(
+                "number of seams should be smaller than width, got 2 and 2"
+            )),
+            arr.remove_seams(&[vec![0, 0], vec![1, 1]])
+                .map_err(|e| format!("{}", e))
+        );
+    }
+
+    #[test]
+    fn horizontal_seams_removal() {
+        let mut arr = Array2d::new(4, (0..20).collect()).unwrap();
+        let mut arr_transposed = arr.clone();
+        let seams = vec![vec![4, 3, 2, 1], vec![0, 1, 1, 0]];
+        arr.remove_horizontal_seams(&seams).unwrap();
+        arr_transposed.transpose();
+        arr_transposed.remove_seams(&seams).unwrap();
+        arr_transposed.transpose();
+        assert_eq!(arr_transposed, arr);
+        assert_eq!(
+            Err(String::from(
+                "seams should not overlap, got row 1 twice in column 1"
+            )),
+            arr.remove_horizontal_seams(&[vec![0, 1, 2, 2], vec![1, 1, 0, 0]])
+                .map_err(|e| format!("{}", e))
+        );
+    }
+
+    #[test]
+    fn seam_insertion() {
+        let mut arr = Array2d::new(3, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]).unwrap();
+        let seam = vec![1, 2, 1];
+        arr.remove_seam(&seam).unwrap();
+        arr.insert_seam(&seam, &[2, 6, 8]).unwrap();
+        assert_eq!(
+            Array2d::new(3, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]).unwrap(),
+            arr
+        );
+    }
+
+    #[test]
+    fn transposition_blocks() {
+        let (width, height) = (TRANSPOSE_BLOCK * 2 + 3, TRANSPOSE_BLOCK + 5);
+        let mut arr = Array2d::new(width, (0..width * height).collect()).unwrap();
+        arr.transpose();
+        assert_eq!((height, width), arr.dimensions());
+        for x in 0..height {
+            for y in 0..width {
+                assert_eq!(y + x * width, arr[(x, y)]);
+            }
+        }
+    }
+
+    #[test]
+    fn seam_removal_horizontal_native() {
+        let mut arr = Array2d::new(3, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]).unwrap();
+        let seam = vec![1, 2, 1];
+        arr.remove_horizontal_seam(&seam).unwrap();
+        assert_eq!(Array2d::new(3, vec![1, 2, 3, 7, 5, 9]).unwrap(), arr);
+    }
+
+    #[test]
+    fn seam_incompatible_horizontal() {
+        let mut arr = Array2d::new(2, vec![1, 2, 3, 4, 5, 6]).unwrap();
+        assert_eq!(
+            Err(String::from(
+                "seam length and image width should be equal, got 3 and 2"
+            )),
+            arr.remove_horizontal_seam(&[1, 2, 1])
+                .map_err(|e| format!("{}", e))
+        );
+    }
+
     #[test]
     fn seam_incompatible() {
         let mut arr = Array2d::new(3, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]).unwrap();
@@ -258,6 +855,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn positions() {
+        assert_eq!(
+            vec![(0, 0), (1, 0), (0, 1), (1, 1), (0, 2), (1, 2)],
+            positions_from_dimensions(2, 3).unwrap().into_raw_data()
+        );
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn ndarray_conversions() {
+        let array = Array2d::new(3, (0..6).collect::<Vec<u32>>()).unwrap();
+        let expected = ndarray::arr2(&[[0, 1, 2], [3, 4, 5]]);
+        assert_eq!(expected.view(), array.as_ndarray());
+        assert_eq!(expected, array.clone().into_ndarray());
+        assert_eq!(array, Array2d::from_ndarray(expected.clone()).unwrap());
+        assert_eq!(array, Array2d::from_ndarray_view(expected.view()).unwrap());
+        // transposed arrays are not in standard layout, their elements being copied in order
+        let transposed = Array2d::new(2, vec![0, 3, 1, 4, 2, 5]).unwrap();
+        assert_eq!(
+            transposed,
+            Array2d::from_ndarray(expected.clone().reversed_axes()).unwrap()
+        );
+        assert_eq!(
+            transposed,
+            Array2d::from_ndarray_view(expected.t()).unwrap()
+        );
+        let sliced = ndarray::arr2(&[[9, 9, 9], [0, 1, 2], [3, 4, 5], [9, 9, 9]]);
+        assert_eq!(
+            array,
+            Array2d::from_ndarray(sliced.slice_move(ndarray::s![1..3, ..])).unwrap()
+        );
+        assert_eq!(
+            Err(String::from("arrays must not be empty, got 0x2")),
+            Array2d::from_ndarray(Array2::<u32>::zeros((2, 0))).map_err(|e| format!("{}", e))
+        );
+    }
+
+    #[cfg(feature = "image")]
     #[test]
     fn from_image() {
         let mut img = RgbImage::new(2, 2);
@@ -281,6 +917,7 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "image")]
     #[test]
     fn to_image() {
         let width = 3;
@@ -0,0 +1,208 @@
+use std::convert::TryFrom;
+use std::error::Error;
+use std::str::FromStr;
+
+use image::RgbImage;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum AspectPolicy {
+    #[default]
+    Remove,
+    Insert,
+}
+
+impl FromStr for AspectPolicy {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "remove" => Ok(AspectPolicy::Remove),
+            "insert" => Ok(AspectPolicy::Insert),
+            _ => Err(format!("aspect policy must be remove or insert, got {}", s).into()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Aspect {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Aspect {
+    pub fn new(width: u32, height: u32) -> Result<Self, Box<dyn Error>> {
+        if width == 0 || height == 0 {
+            return Err(format!(
+                "aspect ratio terms must be positive, got {}:{}",
+                width, height
+            )
+            .into());
+        }
+        Ok(Self { width, height })
+    }
+
+    pub fn target_dimensions(
+        &self,
+        width: u32,
+        height: u32,
+        policy: AspectPolicy,
+    ) -> Result<(u32, u32), Box<dyn Error>> {
+        let (width_64, height_64) = (width as u64, height as u64);
+        let (aspect_width, aspect_height) = (self.width as u64, self.height as u64);
+        let fit = |value: u64| {
+            u32::try_from(value.max(1)).map_err(|_| {
+                format!(
+                    "aspect ratio {}:{} of a {}x{} image needs a side of {} pixels, more than {}",
+                    self.width,
+                    self.height,
+                    width,
+                    height,
+                    value,
+                    u32::MAX
+                )
+            })
+        };
+        let fit_width = || fit((height_64 * aspect_width + aspect_height / 2) / aspect_height);
+        let fit_height = || fit((width_64 * aspect_height + aspect_width / 2) / aspect_width);
+        let too_wide = width_64 * aspect_height > height_64 * aspect_width;
+        Ok(match (policy, too_wide) {
+            (AspectPolicy::Remove, true) => (fit_width()?, height),
+            (AspectPolicy::Remove, false) => (width, fit_height()?),
+            (AspectPolicy::Insert, true) => (width, fit_height()?),
+            (AspectPolicy::Insert, false) => (fit_width()?, height),
+        })
+    }
+}
+
+impl FromStr for Aspect {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some((width, height)) => Self::new(width.parse()?, height.parse()?),
+            None => Err(format!("aspect ratio must be in the form W:H, got {}", s).into()),
+        }
+    }
+}
+
+pub fn seamcarve_aspect(
+    img: &RgbImage,
+    aspect: Aspect,
+    policy: AspectPolicy,
+) -> Result<RgbImage, Box<dyn Error>> {
+    let (width, height) = img.dimensions();
+    let (new_width, new_height) = aspect.target_dimensions(width, height, policy)?;
+    crate::seamcarve(img, new_width, new_height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::gradient_img;
+
+    #[test]
+    fn aspect_parse() {
+        assert_eq!(Aspect::new(16, 9).unwrap(), "16:9".parse().unwrap());
+        assert_eq!(
+            Err(String::from(
+                "aspect ratio must be in the form W:H, got 16x9"
+            )),
+            "16x9".parse::<Aspect>().map_err(|e| format!("{}", e))
+        );
+        assert_eq!(
+            Err(String::from("aspect ratio terms must be positive, got 0:9")),
+            "0:9".parse::<Aspect>().map_err(|e| format!("{}", e))
+        );
+    }
+
+    #[test]
+    fn target_remove() {
+        let square = Aspect::new(1, 1).unwrap();
+        assert_eq!(
+            (1080, 1080),
+            square
+                .target_dimensions(1920, 1080, AspectPolicy::Remove)
+                .unwrap()
+        );
+        assert_eq!(
+            (1080, 1080),
+            square
+                .target_dimensions(1080, 1920, AspectPolicy::Remove)
+                .unwrap()
+        );
+        let wide = Aspect::new(16, 9).unwrap();
+        assert_eq!(
+            (1428, 803),
+            wide.target_dimensions(1428, 968, AspectPolicy::Remove)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn target_insert() {
+        let square = Aspect::new(1, 1).unwrap();
+        assert_eq!(
+            (1920, 1920),
+            square
+                .target_dimensions(1920, 1080, AspectPolicy::Insert)
+                .unwrap()
+        );
+        let wide = Aspect::new(16, 9).unwrap();
+        assert_eq!(
+            (1721, 968),
+            wide.target_dimensions(1428, 968, AspectPolicy::Insert)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn target_out_of_range() {
+        let extreme = Aspect::new(u32::MAX, 1).unwrap();
+        assert_eq!(
+            Err(format!(
+                "aspect ratio {}:1 of a 4x3 image needs a side of {} pixels, more than {}",
+                u32::MAX,
+                3 * u32::MAX as u64,
+                u32::MAX
+            )),
+            extreme
+                .target_dimensions(4, 3, AspectPolicy::Insert)
+                .map_err(|e| format!("{}", e))
+        );
+        assert_eq!(
+            (4, 1),
+            extreme
+                .target_dimensions(4, 3, AspectPolicy::Remove)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn target_unchanged() {
+        let wide = Aspect::new(16, 9).unwrap();
+        for &policy in &[AspectPolicy::Remove, AspectPolicy::Insert] {
+            assert_eq!(
+                (1920, 1080),
+                wide.target_dimensions(1920, 1080, policy).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn carve_aspect() {
+        let img = gradient_img(12, 8);
+        let square = Aspect::new(1, 1).unwrap();
+        assert_eq!(
+            (8, 8),
+            seamcarve_aspect(&img, square, AspectPolicy::Remove)
+                .unwrap()
+                .dimensions()
+        );
+        assert_eq!(
+            (12, 12),
+            seamcarve_aspect(&img, square, AspectPolicy::Insert)
+                .unwrap()
+                .dimensions()
+        );
+    }
+}
@@ -0,0 +1,26 @@
+use std::{env, process};
+
+use rsc::server::{self, ServerConfig};
+
+#[cfg(not(tarpaulin_include))]
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let config = ServerConfig::new(&args).unwrap_or_else(|err| {
+        eprintln!("Problem parsing arguments: {}", err);
+        process::exit(1)
+    });
+
+    let http = tiny_http::Server::http(&config.addr).unwrap_or_else(|err| {
+        eprintln!("Problem listening on {}: {}", config.addr, err);
+        process::exit(1)
+    });
+    println!(
+        "listening on {} with {} workers",
+        config.addr, config.options.workers
+    );
+    if let Err(e) = server::serve(&http, &config.options) {
+        eprintln!("Application error: {}", e);
+        process::exit(1);
+    }
+}
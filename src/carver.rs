@@ -0,0 +1,811 @@
+use std::borrow::Cow;
+use std::error::Error;
+use std::str::FromStr;
+
+use image::{GrayImage, RgbImage};
+
+use crate::array::{self, Array2d};
+use crate::energy::{self, Border, Energy, EnergyOptions};
+use crate::seam::{self, SeamOptions, TieBreak};
+use crate::session::Session;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Order {
+    #[default]
+    VerticalFirst,
+    HorizontalFirst,
+    Interleaved,
+}
+
+impl FromStr for Order {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "vertical-first" => Ok(Order::VerticalFirst),
+            "horizontal-first" => Ok(Order::HorizontalFirst),
+            "interleaved" => Ok(Order::Interleaved),
+            _ => Err(format!(
+                "order must be vertical-first, horizontal-first or interleaved, got {}",
+                s
+            )
+            .into()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Options {
+    pub energy: EnergyOptions,
+    pub seam: SeamOptions,
+    pub order: Order,
+    pub threads: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            energy: EnergyOptions::default(),
+            seam: SeamOptions::default(),
+            order: Order::default(),
+            threads: 1,
+        }
+    }
+}
+
+pub struct Carver<'a> {
+    img: &'a RgbImage,
+    mask: Option<&'a GrayImage>,
+    options: Options,
+    progress: Option<Box<dyn FnMut(u32, u32) + 'a>>,
+    positions: Option<Array2d<(u32, u32)>>,
+    energy_map: Option<Array2d<u32>>,
+}
+
+impl<'a> Carver<'a> {
+    pub fn new(img: &'a RgbImage) -> Self {
+        Self {
+            img,
+            mask: None,
+            options: Options::default(),
+            progress: None,
+            positions: None,
+            energy_map: None,
+        }
+    }
+
+    pub fn energy(mut self, energy: Energy) -> Self {
+        self.options.energy.energy = energy;
+        self.invalidate()
+    }
+
+    pub fn border(mut self, border: Border) -> Self {
+        self.options.energy.border = border;
+        self.invalidate()
+    }
+
+    pub fn protect(mut self, mask: &'a GrayImage) -> Self {
+        self.mask = Some(mask);
+        self.invalidate()
+    }
+
+    pub fn seams_per_pass(mut self, seams_per_pass: usize) -> Self {
+        self.options.seam.seams_per_pass = seams_per_pass.max(1);
+        self
+    }
+
+    pub fn connectivity(mut self, connectivity: usize) -> Self {
+        self.options.seam.connectivity = connectivity.max(1);
+        self
+    }
+
+    pub fn strip(mut self, strip: usize) -> Self {
+        self.options.seam.strip = strip.max(1);
+        self
+    }
+
+    pub fn tie_break(mut self, tie_break: TieBreak) -> Self {
+        self.options.seam.tie_break = tie_break;
+        self
+    }
+
+    pub fn diagonal_penalty(mut self, weight: u32) -> Self {
+        self.options.seam.diagonal_penalty = weight;
+        self
+    }
+
+    pub fn proximity_penalty(mut self, weight: u32) -> Self {
+        self.options.seam.proximity_penalty = weight;
+        self
+    }
+
+    pub fn order(mut self, order: Order) -> Self {
+        self.options.order = order;
+        self
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.options.threads = threads.max(1);
+        self
+    }
+
+    // replaces every option at once, the protected pixels of the energy options included
+    pub fn with_options(mut self, mut options: Options) -> Self {
+        options.seam.seams_per_pass = options.seam.seams_per_pass.max(1);
+        options.seam.connectivity = options.seam.connectivity.max(1);
+        options.seam.strip = options.seam.strip.max(1);
+        options.threads = options.threads.max(1);
+        self.options = options;
+        self.invalidate()
+    }
+
+    pub fn progress<F: FnMut(u32, u32) + 'a>(mut self, callback: F) -> Self {
+        self.progress = Some(Box::new(callback));
+        self
+    }
+
+    pub fn options(&self) -> &Options {
+        &self.options
+    }
+
+    pub fn resize(&mut self, new_width: u32, new_height: u32) -> Result<RgbImage, Box<dyn Error>> {
+        Ok(self.carve(new_width, new_height, None)?.0)
+    }
+
+    // carves an alpha channel along with the image, the seams being found on the image only
+    pub fn resize_with_alpha(
+        &mut self,
+        alpha: &GrayImage,
+        new_width: u32,
+        new_height: u32,
+    ) -> Result<(RgbImage, GrayImage), Box<dyn Error>> {
+        if alpha.dimensions() != self.img.dimensions() {
+            return Err(format!(
+                "alpha and image dimensions should be equal, got {:?} and {:?}",
+                alpha.dimensions(),
+                self.img.dimensions()
+            )
+            .into());
+        }
+        let (img_carved, alpha_carved) = self.carve(new_width, new_height, Some(alpha))?;
+        Ok((img_carved, alpha_carved.unwrap()))
+    }
+
+    fn carve(
+        &mut self,
+        new_width: u32,
+        new_height: u32,
+        alpha: Option<&GrayImage>,
+    ) -> Result<(RgbImage, Option<GrayImage>), Box<dyn Error>> {
+        let img = self.img;
+        let (width, height) = img.dimensions();
+        check_dimensions((width, height), (new_width, new_height))?;
+
+        if (new_width == width) && (new_height == height) {
+            return Ok((img.clone(), alpha.cloned()));
+        }
+
+        self.prepare()?;
+        let mut positions = self.positions.clone().unwrap();
+        let mut energy_map = self.energy_map.clone().unwrap();
+        let mut options = self.options.clone();
+
+        let vertical_to_remove = width.saturating_sub(new_width);
+        let horizontal_to_remove = height.saturating_sub(new_height);
+        let vertical_to_insert = new_width.saturating_sub(width);
+        let horizontal_to_insert = new_height.saturating_sub(height);
+        let mut progress = Progress {
+            done: 0,
+            total: vertical_to_remove
+                + horizontal_to_remove
+                + vertical_to_insert
+                + horizontal_to_insert,
+            callback: self.progress.as_deref_mut(),
+        };
+
+        let mut carve = |to_remove, horizontal| {
+            carve_seams(
+                &mut energy_map,
+                img,
+                &mut positions,
+                to_remove,
+                horizontal,
+                &options,
+                &mut progress,
+            )
+        };
+        match self.options.order {
+            Order::VerticalFirst => {
+                carve(vertical_to_remove, false)?;
+                carve(horizontal_to_remove, true)?;
+            }
+            Order::HorizontalFirst => {
+                carve(horizontal_to_remove, true)?;
+                carve(vertical_to_remove, false)?;
+            }
+            Order::Interleaved => {
+                for i in 0..vertical_to_remove.max(horizontal_to_remove) {
+                    if i < vertical_to_remove {
+                        carve(1, false)?;
+                    }
+                    if i < horizontal_to_remove {
+                        carve(1, true)?;
+                    }
+                }
+            }
+        }
+
+        let mut img_carved = array::filter_image_by_positions(img, &positions);
+        let mut alpha_carved =
+            alpha.map(|alpha| array::filter_image_by_positions(alpha, &positions));
+        if let Some(mask) = &options.energy.protect {
+            options.energy.protect = Some(array::filter_array_by_positions(mask, &positions));
+        }
+
+        if vertical_to_insert > 0 {
+            positions = array::positions_from_image(&img_carved)?;
+            let seams = insert_seams(
+                &mut energy_map,
+                &img_carved,
+                &mut positions,
+                vertical_to_insert,
+                false,
+                &options,
+                &mut progress,
+            )?;
+            img_carved = seam::insert_vertical_seams(&img_carved, &seams);
+            alpha_carved = alpha_carved.map(|alpha| seam::insert_vertical_seams(&alpha, &seams));
+            if let Some(mask) = &options.energy.protect {
+                options.energy.protect = Some(insert_mask_vertical(mask, &seams));
+            }
+        }
+
+        if horizontal_to_insert > 0 {
+            positions = array::positions_from_image(&img_carved)?;
+            energy_map = energy::get_energy_img_parallel(
+                &img_carved,
+                &positions,
+                &options.energy,
+                options.threads,
+            )?;
+            let seams = insert_seams(
+                &mut energy_map,
+                &img_carved,
+                &mut positions,
+                horizontal_to_insert,
+                true,
+                &options,
+                &mut progress,
+            )?;
+            img_carved = seam::insert_horizontal_seams(&img_carved, &seams);
+            alpha_carved = alpha_carved.map(|alpha| seam::insert_horizontal_seams(&alpha, &seams));
+        }
+
+        Ok((img_carved, alpha_carved))
+    }
+
+    pub fn into_session(mut self) -> Result<Session<'a>, Box<dyn Error>> {
+        self.prepare()?;
+        Ok(Session::from_parts(
+            self.img,
+            self.options.energy,
+            self.options.seam,
+            self.positions.unwrap(),
+            self.energy_map.unwrap(),
+        ))
+    }
+
+    fn invalidate(mut self) -> Self {
+        self.positions = None;
+        self.energy_map = None;
+        self
+    }
+
+    fn prepare(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.energy_map.is_some() {
+            return Ok(());
+        }
+        // a mask given to protect replaces the one of the options, if any
+        if let Some(mask) = self.mask {
+            self.options.energy.protect = Some(Array2d::from_mask(mask)?);
+        }
+        if let Some(protect) = &self.options.energy.protect {
+            let (width, height) = self.img.dimensions();
+            if protect.dimensions() != (width as usize, height as usize) {
+                return Err(format!(
+                    "mask and image dimensions should be equal, got {:?} and {:?}",
+                    protect.dimensions(),
+                    (width, height)
+                )
+                .into());
+            }
+        }
+        let positions = array::positions_from_image(self.img)?;
+        self.energy_map = Some(energy::get_energy_img_parallel(
+            self.img,
+            &positions,
+            &self.options.energy,
+            self.options.threads,
+        )?);
+        self.positions = Some(positions);
+        Ok(())
+    }
+}
+
+pub(crate) fn check_dimensions(
+    (width, height): (u32, u32),
+    (new_width, new_height): (u32, u32),
+) -> Result<(), Box<dyn Error>> {
+    if new_width == 0 {
+        return Err(format!("new_width must be positive, got {}", new_width).into());
+    } else if new_width >= 2 * width {
+        return Err(format!(
+            "new_width  cannot be twice the original width or more, got {} and {}",
+            new_width, width
+        )
+        .into());
+    }
+
+    if new_height == 0 {
+        return Err(format!("new_height must be positive, got {}", new_height).into());
+    } else if new_height >= 2 * height {
+        return Err(format!(
+            "new_height cannot be twice the original height or more, got {} and {}",
+            new_height, height
+        )
+        .into());
+    }
+    Ok(())
+}
+
+struct Progress<'a, 'b> {
+    done: u32,
+    total: u32,
+    callback: Option<&'b mut (dyn FnMut(u32, u32) + 'a)>,
+}
+
+impl Progress<'_, '_> {
+    fn step(&mut self) {
+        self.done += 1;
+        if let Some(callback) = self.callback.as_mut() {
+            callback(self.done, self.total);
+        }
+    }
+}
+
+// each pass removes up to seams_per_pass disjoint seams at once, the energy being updated around
+// a single seam and recomputed after several; horizontal seams are found and removed along the
+// columns, without transposing
+fn carve_seams(
+    energy_map: &mut Array2d<u32>,
+    img: &RgbImage,
+    positions: &mut Array2d<(u32, u32)>,
+    to_remove: u32,
+    horizontal: bool,
+    options: &Options,
+    progress: &mut Progress,
+) -> Result<(), Box<dyn Error>> {
+    let mut removed = 0;
+    while removed < to_remove {
+        let to_find = options
+            .seam
+            .seams_per_pass
+            .min((to_remove - removed) as usize);
+        let seams = find_seams(
+            &penalized(energy_map, positions, options),
+            to_find,
+            horizontal,
+            &options.seam,
+        );
+        match horizontal {
+            true => positions.remove_horizontal_seams(&seams)?,
+            false => positions.remove_seams(&seams)?,
+        }
+        match (seams.as_slice(), horizontal) {
+            ([seam], true) => energy::update_energy_img_horizontal_with(
+                energy_map,
+                img,
+                positions,
+                seam,
+                &options.energy,
+            )?,
+            ([seam], false) => {
+                energy::update_energy_img_with(energy_map, img, positions, seam, &options.energy)?
+            }
+            _ => {
+                *energy_map = energy::get_energy_img_parallel(
+                    img,
+                    positions,
+                    &options.energy,
+                    options.threads,
+                )?
+            }
+        }
+        for _ in 0..seams.len() {
+            progress.step();
+        }
+        removed += seams.len() as u32;
+    }
+    Ok(())
+}
+
+// the seams to insert are removed one at a time, each line of the image (a row for vertical
+// seams, a column for horizontal ones) keeping the original indices of its removed pixels
+fn insert_seams(
+    energy_map: &mut Array2d<u32>,
+    img: &RgbImage,
+    positions: &mut Array2d<(u32, u32)>,
+    to_insert: u32,
+    horizontal: bool,
+    options: &Options,
+    progress: &mut Progress,
+) -> Result<Vec<Vec<usize>>, Box<dyn Error>> {
+    let lines = match horizontal {
+        true => positions.width(),
+        false => positions.height(),
+    };
+    let mut seams = Vec::with_capacity(lines);
+    for _ in 0..lines {
+        seams.push(Vec::with_capacity(to_insert as usize));
+    }
+    for _ in 0..to_insert {
+        let seam = find_seams(
+            &penalized(energy_map, positions, options),
+            1,
+            horizontal,
+            &options.seam,
+        )
+        .pop()
+        .unwrap();
+        if horizontal {
+            seam.iter()
+                .enumerate()
+                .for_each(|(x, &y)| seams[x].push(positions[(x, y)].1 as usize));
+            positions.remove_horizontal_seam(&seam)?;
+            energy::update_energy_img_horizontal_with(
+                energy_map,
+                img,
+                positions,
+                &seam,
+                &options.energy,
+            )?;
+        } else {
+            seam.iter()
+                .enumerate()
+                .for_each(|(y, &x)| seams[y].push(positions[(x, y)].0 as usize));
+            positions.remove_seam(&seam)?;
+            energy::update_energy_img_with(energy_map, img, positions, &seam, &options.energy)?;
+        }
+        progress.step();
+    }
+    Ok(seams)
+}
+
+fn find_seams(
+    energy_map: &Array2d<u32>,
+    to_find: usize,
+    horizontal: bool,
+    options: &SeamOptions,
+) -> Vec<Vec<usize>> {
+    match (to_find, horizontal) {
+        (1, true) => vec![seam::find_horizontal_seam_with(energy_map, options)],
+        (1, false) => vec![seam::find_vertical_seam_with(energy_map, options)],
+        (_, true) => seam::find_horizontal_seams_with(energy_map, to_find, options),
+        (_, false) => seam::find_vertical_seams_with(energy_map, to_find, options),
+    }
+}
+
+// previously removed seams are only known through positions, so they are penalized on a copy
+fn penalized<'e>(
+    energy_map: &'e Array2d<u32>,
+    positions: &Array2d<(u32, u32)>,
+    options: &Options,
+) -> Cow<'e, Array2d<u32>> {
+    match options.seam.proximity_penalty {
+        0 => Cow::Borrowed(energy_map),
+        weight => Cow::Owned(seam::proximity_penalized(energy_map, positions, weight)),
+    }
+}
+
+fn insert_mask_vertical(mask: &Array2d<bool>, seams: &[Vec<usize>]) -> Array2d<bool> {
+    let (width, height) = mask.dimensions();
+    let to_insert = seams[0].len();
+    let mut data = Vec::with_capacity((width + to_insert) * height);
+    for (y, to_insert_xs) in seams.iter().enumerate() {
+        let mut to_insert_xs_sorted = to_insert_xs.clone();
+        to_insert_xs_sorted.sort_unstable();
+        let mut already_inserted = 0;
+        for x in 0..width {
+            data.push(mask[(x, y)]);
+            if (already_inserted < to_insert) && (x == to_insert_xs_sorted[already_inserted]) {
+                data.push(mask[(x, y)]);
+                already_inserted += 1;
+            }
+        }
+    }
+    Array2d::new(width + to_insert, data).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::gradient_img;
+    use image::{Luma, Rgb};
+
+    #[test]
+    fn builder_defaults() {
+        let img = gradient_img(4, 4);
+        let carver = Carver::new(&img);
+        assert_eq!(&Options::default(), carver.options());
+        assert_eq!(1, carver.options().threads);
+    }
+
+    #[test]
+    fn resize_orders() {
+        let img = gradient_img(12, 10);
+        for &order in &[
+            Order::VerticalFirst,
+            Order::HorizontalFirst,
+            Order::Interleaved,
+        ] {
+            let mut carver = Carver::new(&img).order(order);
+            assert_eq!((8, 7), carver.resize(8, 7).unwrap().dimensions());
+            assert_eq!((15, 13), carver.resize(15, 13).unwrap().dimensions());
+        }
+    }
+
+    #[test]
+    fn carve_horizontal_native() {
+        let img = gradient_img(12, 10);
+        let mut options = Options::default();
+        for (seams_per_pass, connectivity, strip) in [(1, 1, 1), (1, 2, 1), (1, 1, 3), (3, 1, 1)] {
+            options.seam.seams_per_pass = seams_per_pass;
+            options.seam.connectivity = connectivity;
+            options.seam.strip = strip;
+            let mut progress = Progress {
+                done: 0,
+                total: 8,
+                callback: None,
+            };
+            let mut positions = array::positions_from_image(&img).unwrap();
+            let mut energy_map = energy::get_energy_img(&img, &positions).unwrap();
+            let (mut positions_native, mut energy_map_native) =
+                (positions.clone(), energy_map.clone());
+            carve_seams(
+                &mut energy_map_native,
+                &img,
+                &mut positions_native,
+                4,
+                true,
+                &options,
+                &mut progress,
+            )
+            .unwrap();
+            // the same seams removed as vertical ones of the transposed arrays
+            positions.transpose();
+            energy_map.transpose();
+            carve_seams(
+                &mut energy_map,
+                &img,
+                &mut positions,
+                4,
+                false,
+                &options,
+                &mut progress,
+            )
+            .unwrap();
+            positions.transpose();
+            energy_map.transpose();
+            assert_eq!(positions, positions_native);
+            assert_eq!(energy_map, energy_map_native);
+        }
+    }
+
+    #[test]
+    fn resize_threads() {
+        let img = gradient_img(12, 10);
+        let carved_serial = Carver::new(&img).resize(9, 8).unwrap();
+        let carved_parallel = Carver::new(&img).threads(4).resize(9, 8).unwrap();
+        assert_eq!(carved_serial, carved_parallel);
+    }
+
+    #[test]
+    fn resize_seams_per_pass() {
+        let img = gradient_img(12, 10);
+        let carver = Carver::new(&img).seams_per_pass(0);
+        assert_eq!(1, carver.options().seam.seams_per_pass);
+        for &order in &[Order::VerticalFirst, Order::HorizontalFirst] {
+            let carved = Carver::new(&img)
+                .seams_per_pass(4)
+                .order(order)
+                .resize(5, 7)
+                .unwrap();
+            assert_eq!((5, 7), carved.dimensions());
+        }
+    }
+
+    #[test]
+    fn resize_seams_per_pass_disjoint() {
+        // the red channel identifies the column, so that each carved row must keep distinct
+        // pixels of the original one, in order, if the seams of each pass are disjoint
+        let img = gradient_img(12, 10);
+        for &seams_per_pass in &[1, 3, 4] {
+            let carved = Carver::new(&img)
+                .seams_per_pass(seams_per_pass)
+                .resize(5, 10)
+                .unwrap();
+            assert_eq!((5, 10), carved.dimensions());
+            for y in 0..10 {
+                let columns: Vec<u32> = (0..5)
+                    .map(|x| {
+                        (0..12)
+                            .find(|&i| img.get_pixel(i, y) == carved.get_pixel(x, y))
+                            .unwrap()
+                    })
+                    .collect();
+                assert!(columns.windows(2).all(|pair| pair[0] < pair[1]));
+            }
+        }
+    }
+
+    #[test]
+    fn resize_connectivity_strip() {
+        let img = gradient_img(12, 10);
+        let carver = Carver::new(&img).connectivity(0).strip(0);
+        assert_eq!(1, carver.options().seam.connectivity);
+        assert_eq!(1, carver.options().seam.strip);
+        for &order in &[Order::VerticalFirst, Order::Interleaved] {
+            let carved = Carver::new(&img)
+                .connectivity(2)
+                .strip(3)
+                .order(order)
+                .resize(7, 13)
+                .unwrap();
+            assert_eq!((7, 13), carved.dimensions());
+        }
+    }
+
+    #[test]
+    fn resize_tie_break() {
+        let img = RgbImage::from_pixel(12, 10, Rgb([90, 140, 200]));
+        let carved = Carver::new(&img)
+            .tie_break(TieBreak::Center)
+            .resize(8, 7)
+            .unwrap();
+        assert_eq!((8, 7), carved.dimensions());
+        for seed in [0, 7] {
+            let carve = || {
+                Carver::new(&gradient_img(12, 10))
+                    .tie_break(TieBreak::Random(seed))
+                    .resize(8, 7)
+                    .unwrap()
+            };
+            assert_eq!(carve(), carve());
+        }
+    }
+
+    #[test]
+    fn resize_penalties() {
+        let img = gradient_img(12, 10);
+        let carved = Carver::new(&img).resize(8, 7).unwrap();
+        let carved_zero = Carver::new(&img)
+            .diagonal_penalty(0)
+            .proximity_penalty(0)
+            .resize(8, 7)
+            .unwrap();
+        assert_eq!(carved, carved_zero);
+        for &order in &[Order::VerticalFirst, Order::Interleaved] {
+            let carved = Carver::new(&img)
+                .diagonal_penalty(500)
+                .proximity_penalty(500)
+                .seams_per_pass(2)
+                .order(order)
+                .resize(8, 7)
+                .unwrap();
+            assert_eq!((8, 7), carved.dimensions());
+        }
+    }
+
+    #[test]
+    fn resize_protect() {
+        let img = gradient_img(12, 10);
+        let mask = GrayImage::from_fn(12, 10, |x, _| Luma([if x < 6 { 255 } else { 0 }]));
+        let carved = Carver::new(&img).protect(&mask).resize(8, 10).unwrap();
+        for y in 0..10 {
+            for x in 0..6 {
+                assert_eq!(img.get_pixel(x, y), carved.get_pixel(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn resize_protect_incompatible() {
+        let img = gradient_img(12, 10);
+        let mask = GrayImage::new(10, 10);
+        assert_eq!(
+            Err(String::from(
+                "mask and image dimensions should be equal, got (10, 10) and (12, 10)"
+            )),
+            Carver::new(&img)
+                .protect(&mask)
+                .resize(8, 10)
+                .map_err(|e| format!("{}", e))
+        );
+    }
+
+    #[test]
+    fn resize_with_options() {
+        let img = gradient_img(12, 10);
+        let mask = GrayImage::from_fn(12, 10, |x, _| Luma([if x < 6 { 255 } else { 0 }]));
+        let expected = Carver::new(&img).protect(&mask).resize(8, 10).unwrap();
+        let mut options = Options::default();
+        options.energy.protect =
+            Some(Array2d::new(12, mask.pixels().map(|p| p.0[0] > 0).collect()).unwrap());
+        options.seam.seams_per_pass = 0;
+        let mut carver = Carver::new(&img).with_options(options);
+        assert_eq!(1, carver.options().seam.seams_per_pass);
+        assert_eq!(expected, carver.resize(8, 10).unwrap());
+
+        let mut options = Options::default();
+        options.energy.protect = Some(Array2d::new(10, vec![false; 100]).unwrap());
+        assert_eq!(
+            Err(String::from(
+                "mask and image dimensions should be equal, got (10, 10) and (12, 10)"
+            )),
+            Carver::new(&img)
+                .with_options(options)
+                .resize(8, 10)
+                .map_err(|e| format!("{}", e))
+        );
+    }
+
+    #[test]
+    fn resize_with_alpha() {
+        let img = gradient_img(12, 10);
+        // an alpha equal to the red channel must stay equal to it once carved
+        let alpha = GrayImage::from_fn(12, 10, |x, y| Luma([img.get_pixel(x, y).0[0]]));
+        for (new_width, new_height) in [(12, 10), (8, 7), (15, 13), (9, 12)] {
+            let (carved, alpha_carved) = Carver::new(&img)
+                .resize_with_alpha(&alpha, new_width, new_height)
+                .unwrap();
+            assert_eq!(
+                Carver::new(&img).resize(new_width, new_height).unwrap(),
+                carved
+            );
+            for (pixel, alpha) in carved.pixels().zip(alpha_carved.pixels()) {
+                assert_eq!(pixel.0[0], alpha.0[0]);
+            }
+        }
+        assert_eq!(
+            Err(String::from(
+                "alpha and image dimensions should be equal, got (10, 10) and (12, 10)"
+            )),
+            Carver::new(&img)
+                .resize_with_alpha(&GrayImage::new(10, 10), 8, 10)
+                .map(|_| ())
+                .map_err(|e| format!("{}", e))
+        );
+    }
+
+    #[test]
+    fn resize_progress() {
+        let img = gradient_img(12, 10);
+        let mut steps = vec![];
+        Carver::new(&img)
+            .progress(|done, total| steps.push((done, total)))
+            .resize(10, 13)
+            .unwrap();
+        assert_eq!(vec![(1, 5), (2, 5), (3, 5), (4, 5), (5, 5)], steps);
+    }
+
+    #[test]
+    fn order_parse() {
+        assert_eq!(Order::Interleaved, "interleaved".parse().unwrap());
+        assert_eq!(
+            Err(String::from(
+                "order must be vertical-first, horizontal-first or interleaved, got random"
+            )),
+            "random".parse::<Order>().map_err(|e| format!("{}", e))
+        );
+    }
+}
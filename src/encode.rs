@@ -0,0 +1,142 @@
+use std::error::Error;
+use std::io::Cursor;
+
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::{ImageEncoder, ImageFormat, RgbImage};
+
+pub const OUTPUT_FORMATS: [ImageFormat; 5] = [
+    ImageFormat::Png,
+    ImageFormat::Jpeg,
+    ImageFormat::WebP,
+    ImageFormat::Tiff,
+    ImageFormat::Bmp,
+];
+pub const DEFAULT_QUALITY: u8 = 75;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EncodeOptions {
+    pub quality: u8,
+    pub compression: CompressionType,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        Self {
+            quality: DEFAULT_QUALITY,
+            compression: CompressionType::default(),
+        }
+    }
+}
+
+pub fn parse_format(s: &str) -> Result<ImageFormat, Box<dyn Error>> {
+    ImageFormat::from_extension(s)
+        .filter(|format| OUTPUT_FORMATS.contains(format))
+        .ok_or_else(|| format!("unsupported output format, got {}", s).into())
+}
+
+pub fn parse_quality(s: &str) -> Result<u8, Box<dyn Error>> {
+    match s.parse()? {
+        quality @ 1..=100 => Ok(quality),
+        quality => Err(format!("quality must be between 1 and 100, got {}", quality).into()),
+    }
+}
+
+pub fn parse_compression(s: &str) -> Result<CompressionType, Box<dyn Error>> {
+    match s {
+        "fast" => Ok(CompressionType::Fast),
+        "default" => Ok(CompressionType::Default),
+        "best" => Ok(CompressionType::Best),
+        _ => Err(format!("unknown compression level, got {}", s).into()),
+    }
+}
+
+// the quality only applies to jpeg, the compression level to png, and webp is lossless
+pub fn encode(
+    img: &RgbImage,
+    format: ImageFormat,
+    options: &EncodeOptions,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut bytes = Cursor::new(Vec::new());
+    match format {
+        ImageFormat::Jpeg => {
+            JpegEncoder::new_with_quality(&mut bytes, options.quality).encode_image(img)?
+        }
+        ImageFormat::Png => {
+            PngEncoder::new_with_quality(&mut bytes, options.compression, FilterType::Adaptive)
+                .write_image(
+                    img.as_raw(),
+                    img.width(),
+                    img.height(),
+                    image::ColorType::Rgb8,
+                )?
+        }
+        _ if OUTPUT_FORMATS.contains(&format) => img.write_to(&mut bytes, format)?,
+        _ => return Err(format!("unsupported output format, got {:?}", format).into()),
+    }
+    Ok(bytes.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::gradient_img;
+
+    #[test]
+    fn encode_formats() {
+        let img = gradient_img(12, 10);
+        for format in OUTPUT_FORMATS {
+            let bytes = encode(&img, format, &EncodeOptions::default()).unwrap();
+            assert_eq!(format, image::guess_format(&bytes).unwrap());
+            let decoded = image::load_from_memory(&bytes).unwrap().to_rgb8();
+            assert_eq!(img.dimensions(), decoded.dimensions());
+            if format != ImageFormat::Jpeg {
+                assert_eq!(img, decoded);
+            }
+        }
+        assert_eq!(
+            Err(String::from("unsupported output format, got Gif")),
+            encode(&img, ImageFormat::Gif, &EncodeOptions::default())
+                .map(|_| ())
+                .map_err(|e| format!("{}", e))
+        );
+    }
+
+    #[test]
+    fn encode_options() {
+        let img = gradient_img(64, 48);
+        let jpeg = |quality| {
+            let options = EncodeOptions {
+                quality,
+                ..EncodeOptions::default()
+            };
+            encode(&img, ImageFormat::Jpeg, &options).unwrap().len()
+        };
+        assert!(jpeg(10) < jpeg(95));
+        let png = |compression| {
+            let options = EncodeOptions {
+                compression,
+                ..EncodeOptions::default()
+            };
+            encode(&img, ImageFormat::Png, &options).unwrap().len()
+        };
+        assert!(png(CompressionType::Best) <= png(CompressionType::Fast));
+    }
+
+    #[test]
+    fn parse_options() {
+        assert_eq!(ImageFormat::Jpeg, parse_format("jpg").unwrap());
+        assert_eq!(ImageFormat::Tiff, parse_format("tiff").unwrap());
+        assert_eq!(
+            Err(String::from("unsupported output format, got gif")),
+            parse_format("gif").map_err(|e| format!("{}", e))
+        );
+        assert_eq!(90, parse_quality("90").unwrap());
+        assert_eq!(
+            Err(String::from("quality must be between 1 and 100, got 0")),
+            parse_quality("0").map_err(|e| format!("{}", e))
+        );
+        assert_eq!(CompressionType::Best, parse_compression("best").unwrap());
+        assert!(parse_compression("max").is_err());
+    }
+}
@@ -1,30 +1,157 @@
-use std::error::Error;
-use std::mem;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::vec;
+#[cfg(feature = "image")]
+use alloc::vec::Vec;
+use core::error::Error;
+use core::mem;
+use core::ops::Range;
+use core::str::FromStr;
 
-use image::{Pixel, RgbImage};
-use num_traits::ToPrimitive;
+#[cfg(feature = "image")]
+use image::{ImageBuffer, Pixel};
 
 use crate::array::Array2d;
 
-pub fn get_energy_img(
-    img: &RgbImage,
+pub const PROTECTED_ENERGY: u32 = u32::MAX;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Energy {
+    #[default]
+    DualGradient, // sum of squared differences
+    Gradient, // sum of absolute differences
+}
+
+impl FromStr for Energy {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dual-gradient" => Ok(Energy::DualGradient),
+            "gradient" => Ok(Energy::Gradient),
+            _ => Err(format!("energy must be dual-gradient or gradient, got {}", s).into()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Border {
+    #[default]
+    Wrap,
+    Clamp,
+}
+
+impl FromStr for Border {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wrap" => Ok(Border::Wrap),
+            "clamp" => Ok(Border::Clamp),
+            _ => Err(format!("border must be wrap or clamp, got {}", s).into()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct EnergyOptions {
+    pub energy: Energy,
+    pub border: Border,
+    pub protect: Option<Array2d<bool>>, // indexed by original positions
+}
+
+// images the energy is computed on, their pixels being read at original positions
+pub trait EnergyImage {
+    fn channels(&self, position: (u32, u32)) -> &[u8];
+}
+
+impl<const N: usize> EnergyImage for Array2d<[u8; N]> {
+    fn channels(&self, (x, y): (u32, u32)) -> &[u8] {
+        &self[(x as usize, y as usize)]
+    }
+}
+
+#[cfg(feature = "image")]
+impl<P: Pixel<Subpixel = u8>> EnergyImage for ImageBuffer<P, Vec<u8>> {
+    fn channels(&self, (x, y): (u32, u32)) -> &[u8] {
+        self.get_pixel(x, y).channels()
+    }
+}
+
+pub fn get_energy_img<I: EnergyImage + ?Sized>(
+    img: &I,
+    positions: &Array2d<(u32, u32)>,
+) -> Result<Array2d<u32>, Box<dyn Error>> {
+    get_energy_img_with(img, positions, &EnergyOptions::default())
+}
+
+pub fn get_energy_img_with<I: EnergyImage + ?Sized>(
+    img: &I,
     positions: &Array2d<(u32, u32)>,
+    options: &EnergyOptions,
+) -> Result<Array2d<u32>, Box<dyn Error>> {
+    let e = positions
+        .iter_indexed()
+        .map(|((x, y), _)| get_energy_pixel(img, positions, x, y, options))
+        .collect();
+    Array2d::new(positions.width(), e)
+}
+
+#[cfg(not(feature = "threads"))]
+pub fn get_energy_img_parallel<I: EnergyImage + Sync + ?Sized>(
+    img: &I,
+    positions: &Array2d<(u32, u32)>,
+    options: &EnergyOptions,
+    _threads: usize,
+) -> Result<Array2d<u32>, Box<dyn Error>> {
+    // without the threads feature, the energy is computed on the calling thread
+    get_energy_img_with(img, positions, options)
+}
+
+#[cfg(feature = "threads")]
+pub fn get_energy_img_parallel<I: EnergyImage + Sync + ?Sized>(
+    img: &I,
+    positions: &Array2d<(u32, u32)>,
+    options: &EnergyOptions,
+    threads: usize,
 ) -> Result<Array2d<u32>, Box<dyn Error>> {
     let (width, height) = positions.dimensions();
-    let mut e = Vec::with_capacity(width * height);
-    for y in 0..height {
-        for x in 0..width {
-            e.push(get_energy_pixel(img, positions, x, y))
-        }
+    let threads = threads.clamp(1, height.max(1));
+    if threads == 1 {
+        return get_energy_img_with(img, positions, options);
     }
+    let rows_per_thread = height.div_ceil(threads);
+    let mut e = vec![0; width * height];
+    crossbeam_utils::thread::scope(|s| {
+        for (i, chunk) in e.chunks_mut(rows_per_thread * width).enumerate() {
+            s.spawn(move |_| {
+                let offset = i * rows_per_thread * width;
+                for (j, energy) in chunk.iter_mut().enumerate() {
+                    let (x, y) = ((offset + j) % width, (offset + j) / width);
+                    *energy = get_energy_pixel(img, positions, x, y, options);
+                }
+            });
+        }
+    })
+    .map_err(|_| "energy computation thread panicked")?;
     Array2d::new(width, e)
 }
 
-pub fn update_energy_img(
+pub fn update_energy_img<I: EnergyImage + ?Sized>(
+    energy: &mut Array2d<u32>,
+    img: &I,
+    positions: &Array2d<(u32, u32)>,
+    seam: &[usize],
+) -> Result<(), Box<dyn Error>> {
+    update_energy_img_with(energy, img, positions, seam, &EnergyOptions::default())
+}
+
+pub fn update_energy_img_with<I: EnergyImage + ?Sized>(
     energy: &mut Array2d<u32>,
-    img: &RgbImage,
+    img: &I,
     positions: &Array2d<(u32, u32)>,
     seam: &[usize],
+    options: &EnergyOptions,
 ) -> Result<(), Box<dyn Error>> {
     energy.remove_seam(seam)?;
     let (width, height) = positions.dimensions(); // seam already removed
@@ -35,49 +162,166 @@ pub fn update_energy_img(
     for (y, &x) in seam.iter().enumerate() {
         if (y == 0) || (y == height - 1) {
             for i in first..last {
-                energy[(i, y)] = get_energy_pixel(img, positions, i, y);
+                energy[(i, y)] = get_energy_pixel(img, positions, i, y, options);
             }
         }
         let left = x.checked_sub(1).unwrap_or(width - 1);
         let right = x % width;
-        energy[(left, y)] = get_energy_pixel(img, positions, left, y);
-        energy[(right, y)] = get_energy_pixel(img, positions, right, y);
+        energy[(left, y)] = get_energy_pixel(img, positions, left, y, options);
+        energy[(right, y)] = get_energy_pixel(img, positions, right, y, options);
+        for i in steep_range(seam, y) {
+            energy[(i, y)] = get_energy_pixel(img, positions, i, y, options);
+        }
     }
     Ok(())
 }
 
-fn get_energy_pixel(img: &RgbImage, positions: &Array2d<(u32, u32)>, x: usize, y: usize) -> u32 {
+pub fn update_energy_img_horizontal<I: EnergyImage + ?Sized>(
+    energy: &mut Array2d<u32>,
+    img: &I,
+    positions: &Array2d<(u32, u32)>,
+    seam: &[usize],
+) -> Result<(), Box<dyn Error>> {
+    update_energy_img_horizontal_with(energy, img, positions, seam, &EnergyOptions::default())
+}
+
+pub fn update_energy_img_horizontal_with<I: EnergyImage + ?Sized>(
+    energy: &mut Array2d<u32>,
+    img: &I,
+    positions: &Array2d<(u32, u32)>,
+    seam: &[usize],
+    options: &EnergyOptions,
+) -> Result<(), Box<dyn Error>> {
+    energy.remove_horizontal_seam(seam)?;
+    let (width, height) = positions.dimensions(); // seam already removed
+    let (mut first, mut last) = (seam[0], seam[width - 1]); // fine even if last on bottom boundary
+    if first > last {
+        mem::swap(&mut first, &mut last);
+    }
+    for (x, &y) in seam.iter().enumerate() {
+        if (x == 0) || (x == width - 1) {
+            for i in first..last {
+                energy[(x, i)] = get_energy_pixel(img, positions, x, i, options);
+            }
+        }
+        let above = y.checked_sub(1).unwrap_or(height - 1);
+        let below = y % height;
+        energy[(x, above)] = get_energy_pixel(img, positions, x, above, options);
+        energy[(x, below)] = get_energy_pixel(img, positions, x, below, options);
+        for i in steep_range(seam, x) {
+            energy[(x, i)] = get_energy_pixel(img, positions, x, i, options);
+        }
+    }
+    Ok(())
+}
+
+pub fn restore_energy_img_with<I: EnergyImage + ?Sized>(
+    energy: &mut Array2d<u32>,
+    img: &I,
+    positions: &Array2d<(u32, u32)>,
+    seam: &[usize],
+    options: &EnergyOptions,
+) -> Result<(), Box<dyn Error>> {
+    energy.insert_seam(seam, &vec![0; seam.len()])?;
+    let (width, height) = positions.dimensions(); // seam already restored
+    let (mut first, mut last) = (seam[0], seam[height - 1]);
+    if first > last {
+        mem::swap(&mut first, &mut last);
+    }
+    for (y, &x) in seam.iter().enumerate() {
+        if (y == 0) || (y == height - 1) {
+            for i in first..=last {
+                energy[(i, y)] = get_energy_pixel(img, positions, i, y, options);
+            }
+        }
+        let left = x.checked_sub(1).unwrap_or(width - 1);
+        let right = (x + 1) % width;
+        for i in [left, x, right] {
+            energy[(i, y)] = get_energy_pixel(img, positions, i, y, options);
+        }
+        // the restored pixels of the neighbouring rows now lie within the range, at its ends
+        let steep = steep_range(seam, y);
+        for i in steep.start..=steep.end {
+            energy[(i, y)] = get_energy_pixel(img, positions, i, y, options);
+        }
+    }
+    Ok(())
+}
+
+// pixels between the seam and its neighbours on the previous and next rows, whose vertical
+// neighbours are shifted when the seam is steeper than one pixel per row
+fn steep_range(seam: &[usize], i: usize) -> Range<usize> {
+    let x = seam[i];
+    let (mut start, mut end) = (x, x);
+    for &next in [i.wrapping_sub(1), i + 1]
+        .iter()
+        .filter_map(|&j| seam.get(j))
+    {
+        if x.abs_diff(next) > 1 {
+            start = start.min(next);
+            end = end.max(next);
+        }
+    }
+    start..end
+}
+
+fn get_energy_pixel<I: EnergyImage + ?Sized>(
+    img: &I,
+    positions: &Array2d<(u32, u32)>,
+    x: usize,
+    y: usize,
+    options: &EnergyOptions,
+) -> u32 {
+    if let Some(mask) = &options.protect {
+        let (px, py) = positions[(x, y)];
+        if mask[(px as usize, py as usize)] {
+            return PROTECTED_ENERGY;
+        }
+    }
     let (width, height) = positions.dimensions();
-    let above = y.checked_sub(1).unwrap_or(height - 1);
-    let below = (y + 1) % height;
-    let left = x.checked_sub(1).unwrap_or(width - 1);
-    let right = (x + 1) % width;
-    squared_diff_pixels(img[positions[(x, above)]], img[positions[(x, below)]])
-        + squared_diff_pixels(img[positions[(left, y)]], img[positions[(right, y)]])
+    let (above, below, left, right) = match options.border {
+        Border::Wrap => (
+            y.checked_sub(1).unwrap_or(height - 1),
+            (y + 1) % height,
+            x.checked_sub(1).unwrap_or(width - 1),
+            (x + 1) % width,
+        ),
+        Border::Clamp => (
+            y.saturating_sub(1),
+            (y + 1).min(height - 1),
+            x.saturating_sub(1),
+            (x + 1).min(width - 1),
+        ),
+    };
+    let diff = match options.energy {
+        Energy::DualGradient => squared_diff_pixels,
+        Energy::Gradient => abs_diff_pixels,
+    };
+    let pixel = |position| img.channels(positions[position]);
+    diff(pixel((x, above)), pixel((x, below))) + diff(pixel((left, y)), pixel((right, y)))
 }
 
-fn squared_diff_pixels<T: Pixel>(pixel_1: T, pixel_2: T) -> u32 {
-    let (channels_1, channels_2) = (pixel_1.channels(), pixel_2.channels());
+fn abs_diff_pixels(channels_1: &[u8], channels_2: &[u8]) -> u32 {
     let mut diff = 0;
-    for (channel_1, channel_2) in channels_1.iter().zip(channels_2) {
-        diff += squared_diff_channels(channel_1, channel_2)
+    for (&channel_1, &channel_2) in channels_1.iter().zip(channels_2) {
+        diff += channel_1.abs_diff(channel_2) as u32
     }
     diff
 }
 
-fn squared_diff_channels<T: ToPrimitive>(channel_1: &T, channel_2: &T) -> u32 {
-    let (channel_1, channel_2) = (
-        channel_1.to_i32().unwrap_or(i32::MAX),
-        channel_2.to_i32().unwrap_or(i32::MAX),
-    );
-    i32::pow(channel_1 - channel_2, 2) as u32
+fn squared_diff_pixels(channels_1: &[u8], channels_2: &[u8]) -> u32 {
+    let mut diff = 0;
+    for (&channel_1, &channel_2) in channels_1.iter().zip(channels_2) {
+        diff += (channel_1.abs_diff(channel_2) as u32).pow(2)
+    }
+    diff
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "image"))]
 mod tests {
     use super::*;
     use crate::array::positions_from_image;
-    use image::Rgb;
+    use image::{Rgb, RgbImage};
 
     #[test]
     fn energy_computation_1() {
@@ -239,4 +483,186 @@ mod tests {
         update_energy_img(&mut energy_updated, &img, &positions, &seam_3).unwrap();
         assert_eq!(energy_computed, energy_updated);
     }
+
+    #[test]
+    fn energy_computation_gradient() {
+        let mut img = RgbImage::new(3, 2);
+        img.put_pixel(0, 0, Rgb([0, 0, 0]));
+        img.put_pixel(1, 0, Rgb([10, 0, 0]));
+        img.put_pixel(2, 0, Rgb([30, 0, 0]));
+        img.put_pixel(0, 1, Rgb([0, 5, 0]));
+        img.put_pixel(1, 1, Rgb([10, 5, 0]));
+        img.put_pixel(2, 1, Rgb([30, 5, 0]));
+        let positions = positions_from_image(&img).unwrap();
+        let options = EnergyOptions {
+            energy: Energy::Gradient,
+            border: Border::Clamp,
+            protect: None,
+        };
+        let energy = get_energy_img_with(&img, &positions, &options).unwrap();
+        assert_eq!(vec![15, 35, 25, 15, 35, 25], energy.raw_data());
+    }
+
+    #[test]
+    fn energy_computation_protected() {
+        let img = RgbImage::new(3, 2);
+        let positions = positions_from_image(&img).unwrap();
+        let options = EnergyOptions {
+            protect: Some(Array2d::new(3, vec![false, true, false, false, false, true]).unwrap()),
+            ..EnergyOptions::default()
+        };
+        let energy = get_energy_img_with(&img, &positions, &options).unwrap();
+        assert_eq!(
+            vec![0, PROTECTED_ENERGY, 0, 0, 0, PROTECTED_ENERGY],
+            energy.raw_data()
+        );
+    }
+
+    #[test]
+    fn energy_computation_parallel() {
+        let img = RgbImage::from_fn(7, 5, |x, y| Rgb([(x * 40) as u8, (y * 50) as u8, 7]));
+        let positions = positions_from_image(&img).unwrap();
+        let options = EnergyOptions::default();
+        let energy = get_energy_img_with(&img, &positions, &options).unwrap();
+        for threads in 1..8 {
+            assert_eq!(
+                energy,
+                get_energy_img_parallel(&img, &positions, &options, threads).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn energy_computation_array() {
+        let img = RgbImage::from_fn(7, 5, |x, y| Rgb([(x * 40) as u8, (y * 50) as u8, 7]));
+        let pixels = Array2d::new(7, img.pixels().map(|pixel| pixel.0).collect()).unwrap();
+        let positions = positions_from_image(&img).unwrap();
+        for energy in [Energy::DualGradient, Energy::Gradient] {
+            let options = EnergyOptions {
+                energy,
+                border: Border::Clamp,
+                ..EnergyOptions::default()
+            };
+            assert_eq!(
+                get_energy_img_with(&img, &positions, &options).unwrap(),
+                get_energy_img_with(&pixels, &positions, &options).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn energy_update_clamp() {
+        let img = RgbImage::from_fn(6, 5, |x, y| Rgb([(x * 40) as u8, (y * 50) as u8, 7]));
+        let options = EnergyOptions {
+            border: Border::Clamp,
+            ..EnergyOptions::default()
+        };
+        for seam in [[0, 1, 2, 3, 4], [4, 3, 2, 1, 0], [5, 4, 3, 2, 1]] {
+            let mut positions = positions_from_image(&img).unwrap();
+            let mut energy_updated = get_energy_img_with(&img, &positions, &options).unwrap();
+            positions.remove_seam(&seam).unwrap();
+            let energy_computed = get_energy_img_with(&img, &positions, &options).unwrap();
+            update_energy_img_with(&mut energy_updated, &img, &positions, &seam, &options).unwrap();
+            assert_eq!(energy_computed, energy_updated);
+        }
+    }
+
+    #[test]
+    fn energy_restore() {
+        let img = RgbImage::from_fn(6, 5, |x, y| Rgb([(x * 40) as u8, (y * 50) as u8, 7]));
+        let options = EnergyOptions::default();
+        for seam in [[0, 1, 2, 3, 4], [4, 3, 2, 1, 0], [5, 4, 3, 2, 1]] {
+            let mut positions = positions_from_image(&img).unwrap();
+            let energy_computed = get_energy_img_with(&img, &positions, &options).unwrap();
+            let mut energy_restored = energy_computed.clone();
+            let removed: Vec<(u32, u32)> = seam
+                .iter()
+                .enumerate()
+                .map(|(y, &x)| positions[(x, y)])
+                .collect();
+            positions.remove_seam(&seam).unwrap();
+            update_energy_img_with(&mut energy_restored, &img, &positions, &seam, &options)
+                .unwrap();
+            positions.insert_seam(&seam, &removed).unwrap();
+            restore_energy_img_with(&mut energy_restored, &img, &positions, &seam, &options)
+                .unwrap();
+            assert_eq!(energy_computed, energy_restored);
+        }
+    }
+
+    #[test]
+    fn energy_update_horizontal() {
+        let img = RgbImage::from_fn(5, 6, |x, y| {
+            Rgb([(x * 40) as u8, (y * 50) as u8, (x * y * 9) as u8])
+        });
+        for border in [Border::Wrap, Border::Clamp] {
+            let options = EnergyOptions {
+                border,
+                ..EnergyOptions::default()
+            };
+            for seam in [[0, 1, 2, 3, 4], [4, 3, 2, 1, 0], [5, 4, 3, 2, 1]] {
+                let mut positions = positions_from_image(&img).unwrap();
+                let mut energy_updated = get_energy_img_with(&img, &positions, &options).unwrap();
+                positions.remove_horizontal_seam(&seam).unwrap();
+                let energy_computed = get_energy_img_with(&img, &positions, &options).unwrap();
+                update_energy_img_horizontal_with(
+                    &mut energy_updated,
+                    &img,
+                    &positions,
+                    &seam,
+                    &options,
+                )
+                .unwrap();
+                assert_eq!(energy_computed, energy_updated);
+            }
+        }
+    }
+
+    #[test]
+    fn energy_update_steep() {
+        let img = RgbImage::from_fn(6, 6, |x, y| {
+            Rgb([(x * 40) as u8, (y * 50) as u8, (x * y * 9) as u8])
+        });
+        for border in [Border::Wrap, Border::Clamp] {
+            let options = EnergyOptions {
+                border,
+                ..EnergyOptions::default()
+            };
+            for seam in [[0, 2, 4, 2, 0, 1], [5, 3, 1, 3, 5, 3]] {
+                let mut positions = positions_from_image(&img).unwrap();
+                let mut energy_updated = get_energy_img_with(&img, &positions, &options).unwrap();
+                positions.remove_seam(&seam).unwrap();
+                let energy_computed = get_energy_img_with(&img, &positions, &options).unwrap();
+                update_energy_img_with(&mut energy_updated, &img, &positions, &seam, &options)
+                    .unwrap();
+                assert_eq!(energy_computed, energy_updated);
+
+                let mut positions = positions_from_image(&img).unwrap();
+                let mut energy_updated = get_energy_img_with(&img, &positions, &options).unwrap();
+                positions.remove_horizontal_seam(&seam).unwrap();
+                let energy_computed = get_energy_img_with(&img, &positions, &options).unwrap();
+                update_energy_img_horizontal_with(
+                    &mut energy_updated,
+                    &img,
+                    &positions,
+                    &seam,
+                    &options,
+                )
+                .unwrap();
+                assert_eq!(energy_computed, energy_updated);
+            }
+        }
+    }
+
+    #[test]
+    fn energy_parse() {
+        assert_eq!(Energy::Gradient, "gradient".parse().unwrap());
+        assert_eq!(Border::Clamp, "clamp".parse().unwrap());
+        assert_eq!(
+            Err(String::from(
+                "energy must be dual-gradient or gradient, got sobel"
+            )),
+            "sobel".parse::<Energy>().map_err(|e| format!("{}", e))
+        );
+    }
 }
@@ -0,0 +1,317 @@
+use std::cell::RefCell;
+use std::error::Error;
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+use crate::array::Array2d;
+use crate::carver::{self, Options, Order};
+use crate::energy::{Border, Energy, EnergyOptions};
+use crate::raw::{self, Layout, RawFrame};
+use crate::seam::SeamOptions;
+
+pub const RSC_ENERGY_DUAL_GRADIENT: u32 = 0;
+pub const RSC_ENERGY_GRADIENT: u32 = 1;
+pub const RSC_BORDER_WRAP: u32 = 0;
+pub const RSC_BORDER_CLAMP: u32 = 1;
+pub const RSC_ORDER_VERTICAL_FIRST: u32 = 0;
+pub const RSC_ORDER_HORIZONTAL_FIRST: u32 = 1;
+pub const RSC_ORDER_INTERLEAVED: u32 = 2;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RscStatus {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidStride = 2,
+    InvalidDimensions = 3,
+    InvalidOption = 4,
+    CarvingFailed = 5,
+    Panicked = 6,
+}
+
+/// Carving options, enums being passed as integers so that values unknown to this version of
+/// the library are reported instead of being undefined behaviour.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct RscOptions {
+    pub energy: u32,
+    pub border: u32,
+    pub order: u32,
+    pub threads: u32,
+    pub seams_per_pass: u32,
+    /// width * height bytes, non-zero pixels being protected from carving; may be null
+    pub protect: *const u8,
+}
+
+/// Rgba pixels owned by the library, to be released with rsc_image_free.
+#[repr(C)]
+#[derive(Debug)]
+pub struct RscImage {
+    pub data: *mut u8,
+    pub len: usize,
+    pub width: u32,
+    pub height: u32,
+    pub stride: u32,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+/// Options matching the defaults of the Rust API.
+#[no_mangle]
+pub extern "C" fn rsc_default_options() -> RscOptions {
+    RscOptions {
+        energy: RSC_ENERGY_DUAL_GRADIENT,
+        border: RSC_BORDER_WRAP,
+        order: RSC_ORDER_VERTICAL_FIRST,
+        threads: 1,
+        seams_per_pass: 1,
+        protect: ptr::null(),
+    }
+}
+
+/// Carves rows of `stride` bytes holding `width` rgba pixels each, with the default options
+/// when `options` is null.
+///
+/// # Safety
+///
+/// `rgba` must point to `height` rows of `stride` bytes, `options` to valid options (whose
+/// mask, if any, holds `width * height` bytes) or be null, and `out` to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn rsc_carve(
+    rgba: *const u8,
+    width: u32,
+    height: u32,
+    stride: u32,
+    new_width: u32,
+    new_height: u32,
+    options: *const RscOptions,
+    out: *mut RscImage,
+) -> RscStatus {
+    if rgba.is_null() || out.is_null() {
+        return fail(RscStatus::NullPointer, "rgba and out must not be null");
+    }
+    if (stride as u64) < width as u64 * 4 {
+        let message = format!(
+            "stride must be at least 4 * width, got {} and {}",
+            stride, width
+        );
+        return fail(RscStatus::InvalidStride, &message);
+    }
+    if let Err(e) = carver::check_dimensions((width, height), (new_width, new_height)) {
+        return fail(RscStatus::InvalidDimensions, &e.to_string());
+    }
+    let options = match options.as_ref() {
+        Some(options) => *options,
+        None => rsc_default_options(),
+    };
+    let len = stride as usize * (height as usize - 1) + width as usize * 4;
+    let rgba = slice::from_raw_parts(rgba, len);
+    let protect = match options.protect.is_null() {
+        true => None,
+        false => Some(slice::from_raw_parts(
+            options.protect,
+            width as usize * height as usize,
+        )),
+    };
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        carve(
+            rgba,
+            (width, height),
+            stride,
+            (new_width, new_height),
+            &options,
+            protect,
+        )
+    }));
+    match result {
+        Ok(Ok(data)) => {
+            let len = data.len();
+            *out = RscImage {
+                data: Box::into_raw(data.into_boxed_slice()) as *mut u8,
+                len,
+                width: new_width,
+                height: new_height,
+                stride: new_width * 4,
+            };
+            RscStatus::Ok
+        }
+        Ok(Err((status, message))) => fail(status, &message),
+        Err(_) => fail(RscStatus::Panicked, "carving panicked"),
+    }
+}
+
+/// Releases the pixels of a carved image, leaving it empty.
+///
+/// # Safety
+///
+/// `image` must be null or have been filled by rsc_carve, and not have been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn rsc_image_free(image: *mut RscImage) {
+    if let Some(image) = image.as_mut() {
+        if !image.data.is_null() {
+            let data = ptr::slice_from_raw_parts_mut(image.data, image.len);
+            drop(Box::from_raw(data));
+        }
+        image.data = ptr::null_mut();
+        image.len = 0;
+    }
+}
+
+/// Message of the last error on the calling thread, valid until the next call failing on it.
+#[no_mangle]
+pub extern "C" fn rsc_last_error() -> *const c_char {
+    LAST_ERROR.with(|last_error| last_error.borrow().as_ptr())
+}
+
+fn fail(status: RscStatus, message: &str) -> RscStatus {
+    let message = CString::new(message.replace('\0', "")).unwrap();
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = message);
+    status
+}
+
+fn carve(
+    rgba: &[u8],
+    (width, height): (u32, u32),
+    stride: u32,
+    (new_width, new_height): (u32, u32),
+    options: &RscOptions,
+    protect: Option<&[u8]>,
+) -> Result<Vec<u8>, (RscStatus, String)> {
+    let invalid = |name: &str, value: u32| {
+        (
+            RscStatus::InvalidOption,
+            format!("unknown {}, got {}", name, value),
+        )
+    };
+    let energy = match options.energy {
+        RSC_ENERGY_DUAL_GRADIENT => Energy::DualGradient,
+        RSC_ENERGY_GRADIENT => Energy::Gradient,
+        value => return Err(invalid("energy", value)),
+    };
+    let border = match options.border {
+        RSC_BORDER_WRAP => Border::Wrap,
+        RSC_BORDER_CLAMP => Border::Clamp,
+        value => return Err(invalid("border", value)),
+    };
+    let order = match options.order {
+        RSC_ORDER_VERTICAL_FIRST => Order::VerticalFirst,
+        RSC_ORDER_HORIZONTAL_FIRST => Order::HorizontalFirst,
+        RSC_ORDER_INTERLEAVED => Order::Interleaved,
+        value => return Err(invalid("order", value)),
+    };
+
+    let protect = protect.map(|mask| {
+        let mask = mask.iter().map(|&protected| protected > 0).collect();
+        Array2d::new(width as usize, mask).unwrap()
+    });
+    let options = Options {
+        energy: EnergyOptions {
+            energy,
+            border,
+            protect,
+        },
+        seam: SeamOptions {
+            seams_per_pass: options.seams_per_pass as usize,
+            ..SeamOptions::default()
+        },
+        order,
+        threads: options.threads as usize,
+    };
+    let failed = |e: Box<dyn Error>| (RscStatus::CarvingFailed, e.to_string());
+    let frame =
+        RawFrame::new(rgba, width, height, stride as usize, Layout::Rgba).map_err(failed)?;
+    raw::seamcarve_raw(&frame, new_width, new_height, &options).map_err(failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::gradient_img;
+    use crate::Carver;
+    use image::Rgb;
+    use std::ffi::CStr;
+
+    fn rgba_rows(width: u32, height: u32, stride: u32) -> Vec<u8> {
+        let mut data = vec![0; (stride * height) as usize];
+        for (x, y, &Rgb([r, g, b])) in gradient_img(width, height).enumerate_pixels() {
+            let offset = (y * stride + x * 4) as usize;
+            data[offset..offset + 4].copy_from_slice(&[r, g, b, (x * 20) as u8]);
+        }
+        data
+    }
+
+    fn last_error() -> String {
+        unsafe { CStr::from_ptr(rsc_last_error()) }
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn ffi_carve() {
+        // rows padded to 64 bytes
+        let data = rgba_rows(12, 10, 64);
+        let mut out = RscImage {
+            data: ptr::null_mut(),
+            len: 0,
+            width: 0,
+            height: 0,
+            stride: 0,
+        };
+        let status = unsafe { rsc_carve(data.as_ptr(), 12, 10, 64, 8, 11, ptr::null(), &mut out) };
+        assert_eq!(RscStatus::Ok, status);
+        assert_eq!(
+            (8, 11, 32, 8 * 11 * 4),
+            (out.width, out.height, out.stride, out.len)
+        );
+        let carved = unsafe { slice::from_raw_parts(out.data, out.len) }.to_vec();
+        unsafe { rsc_image_free(&mut out) };
+        assert!(out.data.is_null());
+
+        let expected = Carver::new(&gradient_img(12, 10)).resize(8, 11).unwrap();
+        for (pixel, carved) in expected.pixels().zip(carved.chunks_exact(4)) {
+            assert_eq!(&pixel.0[..], &carved[..3]);
+        }
+    }
+
+    #[test]
+    fn ffi_errors() {
+        let data = rgba_rows(12, 10, 48);
+        let mut out = RscImage {
+            data: ptr::null_mut(),
+            len: 0,
+            width: 0,
+            height: 0,
+            stride: 0,
+        };
+        let carve = |stride, new_width, options: &RscOptions, out: &mut RscImage| unsafe {
+            rsc_carve(data.as_ptr(), 12, 10, stride, new_width, 10, options, out)
+        };
+        let options = rsc_default_options();
+        assert_eq!(RscStatus::InvalidStride, carve(40, 8, &options, &mut out));
+        assert_eq!(
+            "stride must be at least 4 * width, got 40 and 12",
+            last_error()
+        );
+        assert_eq!(
+            RscStatus::InvalidDimensions,
+            carve(48, 0, &options, &mut out)
+        );
+        assert_eq!("new_width must be positive, got 0", last_error());
+        let options = RscOptions {
+            energy: 7,
+            ..rsc_default_options()
+        };
+        assert_eq!(RscStatus::InvalidOption, carve(48, 8, &options, &mut out));
+        assert_eq!("unknown energy, got 7", last_error());
+        assert_eq!(RscStatus::NullPointer, unsafe {
+            rsc_carve(ptr::null(), 12, 10, 48, 8, 10, ptr::null(), &mut out)
+        });
+        assert!(out.data.is_null());
+    }
+}
@@ -1,168 +1,246 @@
-use std::error::Error;
+// the core (arrays, energy and seam search) only needs an allocator, everything built on
+// images needs the standard library; a no_std cdylib cannot be linked, so the crate only goes
+// without std on bare-metal targets, where no cdylib is built
+#![cfg_attr(all(not(any(feature = "std", test)), target_os = "none"), no_std)]
+
+extern crate alloc;
 
+#[cfg(feature = "image")]
+use std::error::Error;
+#[cfg(feature = "fs")]
+use std::fs;
+#[cfg(feature = "fs")]
+use std::io::{self, Cursor, Read, Write};
+#[cfg(feature = "fs")]
+use std::path::Path;
+#[cfg(feature = "fs")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(feature = "fs")]
 use image::io::Reader as ImageReader;
+#[cfg(feature = "image")]
 use image::RgbImage;
 
 pub mod array;
+#[cfg(feature = "image")]
+pub mod aspect;
+#[cfg(feature = "image")]
+pub mod carver;
+#[cfg(feature = "image")]
+pub mod encode;
 pub mod energy;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod maxflow;
+#[cfg(feature = "image")]
+pub mod metadata;
+#[cfg(feature = "fs")]
 pub mod parser;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "image")]
+pub mod raw;
+#[cfg(feature = "image")]
+pub mod retarget;
 pub mod seam;
-
-use array::Array2d;
-
+#[cfg(feature = "image")]
+pub mod sequence;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "image")]
+pub mod session;
+#[cfg(feature = "image")]
+pub mod stereo;
+#[cfg(all(test, feature = "image"))]
+mod test_utils;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "image")]
+pub use aspect::{seamcarve_aspect, Aspect, AspectPolicy};
+#[cfg(feature = "image")]
+pub use carver::Carver;
+#[cfg(feature = "image")]
+pub use metadata::Metadata;
+#[cfg(all(feature = "image", feature = "ndarray"))]
+pub use raw::seamcarve_ndarray;
+#[cfg(feature = "image")]
+pub use raw::{seamcarve_raw, Layout, RawFrame};
+#[cfg(feature = "image")]
+pub use retarget::RetargetMap;
+#[cfg(feature = "image")]
+pub use sequence::carve_sequence;
+#[cfg(feature = "image")]
+pub use session::Session;
+#[cfg(feature = "image")]
+pub use stereo::carve_stereo;
+
+#[cfg(feature = "fs")]
+type Dimensions = (u32, u32);
+
+#[cfg(feature = "fs")]
 #[cfg(not(tarpaulin_include))]
 pub fn run(config: parser::Config) -> Result<(), Box<dyn Error>> {
-    let img_original = ImageReader::open(&config.infile)?.decode()?.to_rgb8();
-    let img_carved = seamcarve(&img_original, config.new_width, config.new_height)?;
-    img_carved.save(config.get_outfile())?;
-    Ok(())
-}
-
-pub fn seamcarve(
-    img: &RgbImage,
-    new_width: u32,
-    new_height: u32,
-) -> Result<RgbImage, Box<dyn Error>> {
-    let (width, height) = img.dimensions();
-    if new_width == 0 {
-        return Err(format!("new_width must be positive, got {}", new_width).into());
-    } else if new_width >= 2 * width {
-        return Err(format!(
-            "new_width  cannot be twice the original width or more, got {} and {}",
-            new_width, width
-        )
-        .into());
+    if config.sequence {
+        return run_sequence(&config);
     }
-
-    if new_height == 0 {
-        return Err(format!("new_height must be positive, got {}", new_height).into());
-    } else if new_height >= 2 * height {
-        return Err(format!(
-            "new_height cannot be twice the original height or more, got {} and {}",
-            new_height, height
-        )
-        .into());
+    let infiles = config.get_infiles()?;
+    if !config.is_batch() {
+        let infile = &infiles[0];
+        process_file(infile, &config.get_outfile(infile)?, &config)?;
+        return Ok(());
     }
 
-    let mut img_carved = img.clone();
-
-    if (new_width == width) && (new_height == height) {
-        return Ok(img_carved);
+    if infiles.is_empty() {
+        return Err("no input files found".into());
     }
-
-    let mut positions = array::positions_from_image(&img_carved)?;
-    let mut energy_map = energy::get_energy_img(&img_carved, &positions)?;
-
-    if new_width < width {
-        let vertical_to_remove = width - new_width;
-        carve_vertical(
-            &mut energy_map,
-            &img_carved,
-            &mut positions,
-            vertical_to_remove,
-        )?;
+    if let Some(outdir) = &config.outdir {
+        fs::create_dir_all(outdir)?;
     }
-
-    if new_height < height {
-        positions.transpose();
-        energy_map.transpose();
-        let horizontal_to_remove = height - new_height;
-        carve_vertical(
-            &mut energy_map,
-            &img_carved,
-            &mut positions,
-            horizontal_to_remove,
-        )?;
-        positions.transpose();
-        energy_map.transpose();
-    }
-
-    img_carved = array::filter_image_by_positions(&img_carved, &positions);
-
-    if new_width > width {
-        positions = array::positions_from_image(&img_carved)?;
-        let vertical_to_insert = new_width - width;
-        img_carved = insert_vertical(
-            &mut energy_map,
-            &img_carved,
-            &mut positions,
-            vertical_to_insert,
-        )?;
+    let outfiles = config.get_outfiles(&infiles);
+    let next = AtomicUsize::new(0);
+    let work = || {
+        let mut results = Vec::new();
+        loop {
+            let i = next.fetch_add(1, Ordering::SeqCst);
+            if i >= infiles.len() {
+                break results;
+            }
+            let result = outfiles[i]
+                .as_ref()
+                .map_err(String::clone)
+                .and_then(|outfile| {
+                    process_file(&infiles[i], outfile, &config).map_err(|e| e.to_string())
+                });
+            results.push((i, result));
+        }
+    };
+    #[cfg(feature = "threads")]
+    let mut results: Vec<_> = crossbeam_utils::thread::scope(|s| {
+        let handles: Vec<_> = (0..config.jobs.min(infiles.len()))
+            .map(|_| s.spawn(|_| work()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+    .map_err(|_| "batch worker thread panicked")?;
+    // without the threads feature, files are carved one after the other
+    #[cfg(not(feature = "threads"))]
+    let mut results = work();
+    results.sort_by_key(|(i, _)| *i);
+
+    let mut failed = 0;
+    for (i, result) in results {
+        match result {
+            Ok(((width, height), (new_width, new_height))) => println!(
+                "{}: {}x{} -> {}x{}, saved to {}",
+                infiles[i].display(),
+                width,
+                height,
+                new_width,
+                new_height,
+                outfiles[i].as_ref().unwrap().display()
+            ),
+            Err(e) => {
+                failed += 1;
+                eprintln!("{}: failed, {}", infiles[i].display(), e)
+            }
+        }
     }
-
-    if new_height > height {
-        positions = array::positions_from_image(&img_carved)?;
-        positions.transpose();
-        energy_map = energy::get_energy_img(&img_carved, &positions)?;
-        let horizontal_to_insert = new_height - height;
-        img_carved = insert_horizontal(
-            &mut energy_map,
-            &img_carved,
-            &mut positions,
-            horizontal_to_insert,
-        )?;
+    match failed {
+        0 => Ok(()),
+        _ => Err(format!("{} of {} files failed", failed, infiles.len()).into()),
     }
-
-    Ok(img_carved)
 }
 
-fn carve_vertical(
-    energy_map: &mut Array2d<u32>,
-    img: &RgbImage,
-    positions: &mut Array2d<(u32, u32)>,
-    to_remove: u32,
-) -> Result<(), Box<dyn Error>> {
-    let mut seam;
-    for _ in 0..to_remove {
-        seam = seam::find_vertical_seam(energy_map);
-        positions.remove_seam(&seam)?;
-        energy::update_energy_img(energy_map, img, positions, &seam)?;
+#[cfg(feature = "fs")]
+#[cfg(not(tarpaulin_include))]
+fn run_sequence(config: &parser::Config) -> Result<(), Box<dyn Error>> {
+    for dir in &config.inputs {
+        let paths = config.get_frames(dir)?;
+        if paths.is_empty() {
+            return Err(format!("no frames found in {}", dir.display()).into());
+        }
+        let mut frames = Vec::with_capacity(paths.len());
+        for path in &paths {
+            frames.push(ImageReader::open(path)?.decode()?.to_rgb8());
+        }
+        let (width, height) = frames[0].dimensions();
+        let (new_width, new_height) = config.size.resolve(width, height)?;
+        let frames_carved = carve_sequence(&frames, new_width, new_height)?;
+        let outdir = config.get_sequence_outdir(dir)?;
+        fs::create_dir_all(&outdir)?;
+        for (path, frame) in paths.iter().zip(&frames_carved) {
+            let name = path
+                .file_name()
+                .ok_or_else(|| format!("invalid frame path {}", path.display()))?;
+            frame.save(outdir.join(name))?;
+        }
+        println!(
+            "{}: {} frames, {}x{} -> {}x{}, saved to {}",
+            dir.display(),
+            frames.len(),
+            width,
+            height,
+            new_width,
+            new_height,
+            outdir.display()
+        );
     }
     Ok(())
 }
 
-fn insert_vertical(
-    energy_map: &mut Array2d<u32>,
-    img: &RgbImage,
-    positions: &mut Array2d<(u32, u32)>,
-    to_insert: u32,
-) -> Result<RgbImage, Box<dyn Error>> {
-    let height = positions.height();
-    let mut seams = Vec::with_capacity(height);
-    for _ in 0..height {
-        seams.push(Vec::with_capacity(to_insert as usize));
+#[cfg(feature = "fs")]
+#[cfg(not(tarpaulin_include))]
+fn process_file(
+    infile: &Path,
+    outfile: &Path,
+    config: &parser::Config,
+) -> Result<(Dimensions, Dimensions), Box<dyn Error>> {
+    let bytes = if infile == Path::new(parser::STDIO) {
+        let mut bytes = Vec::new();
+        io::stdin().lock().read_to_end(&mut bytes)?;
+        bytes
+    } else {
+        fs::read(infile)?
+    };
+    // metadata that cannot be parsed, while the image itself decodes, is dropped
+    let metadata = Metadata::from_bytes(&bytes).unwrap_or_else(|e| {
+        eprintln!(
+            "{}: warning, ignoring unreadable metadata, {}",
+            infile.display(),
+            e
+        );
+        Metadata::default()
+    });
+    let reader = ImageReader::new(Cursor::new(&bytes)).with_guessed_format()?;
+    let input_format = reader.format();
+    let img = reader.decode()?;
+    // the orientation is applied even when stripping metadata, so that the image looks the same
+    let img_original = metadata::apply_orientation(img, metadata.orientation()).to_rgb8();
+    let (width, height) = img_original.dimensions();
+    let (new_width, new_height) = config.size.resolve(width, height)?;
+    let img_carved = seamcarve(&img_original, new_width, new_height)?;
+    let format = config.get_format(outfile, input_format)?;
+    let mut encoded = encode::encode(&img_carved, format, &config.encode)?;
+    if !config.strip_metadata {
+        encoded = metadata.embed(encoded, (new_width, new_height))?;
     }
-    let mut seam;
-    for _ in 0..to_insert {
-        seam = seam::find_vertical_seam(energy_map);
-        seam.iter()
-            .enumerate()
-            .for_each(|(y, &x)| seams[y].push(positions[(x, y)].0 as usize));
-        positions.remove_seam(&seam)?;
-        energy::update_energy_img(energy_map, img, positions, &seam)?;
+    if outfile == Path::new(parser::STDIO) {
+        io::stdout().lock().write_all(&encoded)?;
+    } else {
+        fs::write(outfile, encoded)?;
     }
-    Ok(seam::insert_vertical_seams(img, &seams))
+    Ok(((width, height), (new_width, new_height)))
 }
 
-fn insert_horizontal(
-    energy_map: &mut Array2d<u32>,
+#[cfg(feature = "image")]
+pub fn seamcarve(
     img: &RgbImage,
-    positions: &mut Array2d<(u32, u32)>,
-    to_insert: u32,
+    new_width: u32,
+    new_height: u32,
 ) -> Result<RgbImage, Box<dyn Error>> {
-    let height = positions.height();
-    let mut seams = Vec::with_capacity(height);
-    for _ in 0..height {
-        seams.push(Vec::with_capacity(to_insert as usize));
-    }
-    let mut seam;
-    for _ in 0..to_insert {
-        seam = seam::find_vertical_seam(energy_map);
-        seam.iter()
-            .enumerate()
-            .for_each(|(y, &x)| seams[y].push(positions[(x, y)].1 as usize));
-        positions.remove_seam(&seam)?;
-        energy::update_energy_img(energy_map, img, positions, &seam)?;
-    }
-    Ok(seam::insert_horizontal_seams(img, &seams))
+    Carver::new(img).resize(new_width, new_height)
 }
@@ -0,0 +1,358 @@
+use alloc::collections::VecDeque;
+use alloc::vec;
+use alloc::vec::Vec;
+
+pub const INFINITE: u64 = u64::MAX / 4;
+
+const NONE: usize = usize::MAX;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Tree {
+    Free,
+    Source,
+    Sink,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Parent {
+    Orphan,
+    Terminal,
+    Arc(usize), // arc from the node to its parent
+}
+
+// Boykov-Kolmogorov max-flow, growing search trees from both terminals and reusing them
+// between augmentations
+#[derive(Clone, Debug)]
+pub struct Graph {
+    first_arc: Vec<usize>,
+    source_cap: Vec<u64>,
+    sink_cap: Vec<u64>,
+    tree: Vec<Tree>,
+    parent: Vec<Parent>,
+    timestamp: Vec<usize>,
+    distance: Vec<usize>,
+    head: Vec<usize>,
+    next_arc: Vec<usize>,
+    cap: Vec<u64>, // arcs are stored in pairs, the sister of arc a being a ^ 1
+    flow: u64,
+}
+
+impl Graph {
+    pub fn new(nodes: usize) -> Self {
+        Self {
+            first_arc: vec![NONE; nodes],
+            source_cap: vec![0; nodes],
+            sink_cap: vec![0; nodes],
+            tree: vec![Tree::Free; nodes],
+            parent: vec![Parent::Orphan; nodes],
+            timestamp: vec![0; nodes],
+            distance: vec![0; nodes],
+            head: Vec::new(),
+            next_arc: Vec::new(),
+            cap: Vec::new(),
+            flow: 0,
+        }
+    }
+
+    pub fn with_capacity(nodes: usize, edges: usize) -> Self {
+        let mut graph = Self::new(nodes);
+        graph.head.reserve(2 * edges);
+        graph.next_arc.reserve(2 * edges);
+        graph.cap.reserve(2 * edges);
+        graph
+    }
+
+    pub fn nodes(&self) -> usize {
+        self.first_arc.len()
+    }
+
+    pub fn add_edge(&mut self, from: usize, to: usize, cap: u64, rev_cap: u64) {
+        for (tail, head, cap) in [(from, to, cap), (to, from, rev_cap)] {
+            self.head.push(head);
+            self.next_arc.push(self.first_arc[tail]);
+            self.cap.push(cap);
+            self.first_arc[tail] = self.head.len() - 1;
+        }
+    }
+
+    pub fn add_terminal(&mut self, node: usize, source_cap: u64, sink_cap: u64) {
+        let source_cap = self.source_cap[node] + source_cap;
+        let sink_cap = self.sink_cap[node] + sink_cap;
+        // flow through source -> node -> sink is pushed right away
+        let common = source_cap.min(sink_cap);
+        self.flow += common;
+        self.source_cap[node] = source_cap - common;
+        self.sink_cap[node] = sink_cap - common;
+    }
+
+    pub fn maxflow(&mut self) -> u64 {
+        let mut active = VecDeque::new();
+        let mut orphans = VecDeque::new();
+        for node in 0..self.nodes() {
+            let (tree, parent) = match (self.source_cap[node], self.sink_cap[node]) {
+                (0, 0) => (Tree::Free, Parent::Orphan),
+                (0, _) => (Tree::Sink, Parent::Terminal),
+                _ => (Tree::Source, Parent::Terminal),
+            };
+            self.tree[node] = tree;
+            self.parent[node] = parent;
+            self.timestamp[node] = 0;
+            self.distance[node] = 1;
+            if tree != Tree::Free {
+                active.push_back(node);
+            }
+        }
+        let mut time = 0;
+        while let Some(node) = active.pop_front() {
+            let middle = match self.grow(node, &mut active) {
+                Some(middle) => middle,
+                None => continue,
+            };
+            active.push_front(node); // may still have unexplored neighbours
+            time += 1;
+            self.augment(middle, &mut orphans);
+            while let Some(orphan) = orphans.pop_front() {
+                self.adopt(orphan, time, &mut active, &mut orphans);
+            }
+        }
+        self.flow
+    }
+
+    pub fn is_source(&self, node: usize) -> bool {
+        self.tree[node] == Tree::Source
+    }
+
+    // residual capacity from the node towards the tree it belongs to, or away from it
+    fn tree_cap(&self, arc: usize, tree: Tree) -> u64 {
+        match tree {
+            Tree::Source => self.cap[arc],
+            _ => self.cap[arc ^ 1],
+        }
+    }
+
+    // returns the arc from the source tree to the sink tree, if any
+    fn grow(&mut self, node: usize, active: &mut VecDeque<usize>) -> Option<usize> {
+        let tree = self.tree[node];
+        if tree == Tree::Free {
+            return None;
+        }
+        let mut arc = self.first_arc[node];
+        while arc != NONE {
+            let neighbour = self.head[arc];
+            if self.tree_cap(arc, tree) > 0 {
+                match self.tree[neighbour] {
+                    Tree::Free => {
+                        self.tree[neighbour] = tree;
+                        self.parent[neighbour] = Parent::Arc(arc ^ 1);
+                        self.timestamp[neighbour] = self.timestamp[node];
+                        self.distance[neighbour] = self.distance[node] + 1;
+                        active.push_back(neighbour);
+                    }
+                    other if other != tree => {
+                        return Some(if tree == Tree::Source { arc } else { arc ^ 1 });
+                    }
+                    _ => {}
+                }
+            }
+            arc = self.next_arc[arc];
+        }
+        None
+    }
+
+    fn augment(&mut self, middle: usize, orphans: &mut VecDeque<usize>) {
+        let (source_side, sink_side) = (self.head[middle ^ 1], self.head[middle]);
+        let mut bottleneck = self.cap[middle];
+        let mut node = source_side;
+        while let Parent::Arc(arc) = self.parent[node] {
+            bottleneck = bottleneck.min(self.cap[arc ^ 1]);
+            node = self.head[arc];
+        }
+        bottleneck = bottleneck.min(self.source_cap[node]);
+        let mut node = sink_side;
+        while let Parent::Arc(arc) = self.parent[node] {
+            bottleneck = bottleneck.min(self.cap[arc]);
+            node = self.head[arc];
+        }
+        bottleneck = bottleneck.min(self.sink_cap[node]);
+
+        self.cap[middle] -= bottleneck;
+        self.cap[middle ^ 1] += bottleneck;
+        let mut node = source_side;
+        while let Parent::Arc(arc) = self.parent[node] {
+            self.cap[arc] += bottleneck;
+            self.cap[arc ^ 1] -= bottleneck;
+            if self.cap[arc ^ 1] == 0 {
+                self.parent[node] = Parent::Orphan;
+                orphans.push_back(node);
+            }
+            node = self.head[arc];
+        }
+        self.source_cap[node] -= bottleneck;
+        if self.source_cap[node] == 0 {
+            self.parent[node] = Parent::Orphan;
+            orphans.push_back(node);
+        }
+        let mut node = sink_side;
+        while let Parent::Arc(arc) = self.parent[node] {
+            self.cap[arc ^ 1] += bottleneck;
+            self.cap[arc] -= bottleneck;
+            if self.cap[arc] == 0 {
+                self.parent[node] = Parent::Orphan;
+                orphans.push_back(node);
+            }
+            node = self.head[arc];
+        }
+        self.sink_cap[node] -= bottleneck;
+        if self.sink_cap[node] == 0 {
+            self.parent[node] = Parent::Orphan;
+            orphans.push_back(node);
+        }
+        self.flow += bottleneck;
+    }
+
+    fn adopt(
+        &mut self,
+        orphan: usize,
+        time: usize,
+        active: &mut VecDeque<usize>,
+        orphans: &mut VecDeque<usize>,
+    ) {
+        let tree = self.tree[orphan];
+        let (mut best_arc, mut best_distance) = (NONE, usize::MAX);
+        let mut arc = self.first_arc[orphan];
+        while arc != NONE {
+            let neighbour = self.head[arc];
+            if self.tree[neighbour] == tree && self.tree_cap(arc ^ 1, tree) > 0 {
+                if let Some(distance) = self.origin_distance(neighbour, time) {
+                    if distance < best_distance {
+                        best_arc = arc;
+                        best_distance = distance;
+                    }
+                }
+            }
+            arc = self.next_arc[arc];
+        }
+        if best_arc != NONE {
+            self.parent[orphan] = Parent::Arc(best_arc);
+            self.timestamp[orphan] = time;
+            self.distance[orphan] = best_distance + 1;
+            return;
+        }
+
+        let mut arc = self.first_arc[orphan];
+        while arc != NONE {
+            let neighbour = self.head[arc];
+            if self.tree[neighbour] == tree {
+                if self.tree_cap(arc ^ 1, tree) > 0 {
+                    active.push_back(neighbour);
+                }
+                if self.parent[neighbour] == Parent::Arc(arc ^ 1) {
+                    self.parent[neighbour] = Parent::Orphan;
+                    orphans.push_back(neighbour);
+                }
+            }
+            arc = self.next_arc[arc];
+        }
+        self.tree[orphan] = Tree::Free;
+    }
+
+    // distance from a terminal, or None if the node descends from an orphan
+    fn origin_distance(&mut self, node: usize, time: usize) -> Option<usize> {
+        let mut current = node;
+        let mut distance = 0;
+        loop {
+            if self.timestamp[current] == time {
+                distance += self.distance[current];
+                break;
+            }
+            match self.parent[current] {
+                Parent::Orphan => return None,
+                Parent::Terminal => {
+                    self.timestamp[current] = time;
+                    self.distance[current] = 1;
+                    distance += 1;
+                    break;
+                }
+                Parent::Arc(arc) => {
+                    distance += 1;
+                    current = self.head[arc];
+                }
+            }
+        }
+        // caches the distances along the path for later origin checks
+        let (mut current, mut remaining) = (node, distance);
+        while self.timestamp[current] != time {
+            self.timestamp[current] = time;
+            self.distance[current] = remaining;
+            remaining -= 1;
+            if let Parent::Arc(arc) = self.parent[current] {
+                current = self.head[arc];
+            }
+        }
+        Some(distance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maxflow_simple() {
+        // source -> 0 -> 1 -> sink, with a bottleneck of 2 on the middle edge
+        let mut graph = Graph::new(2);
+        graph.add_terminal(0, 5, 0);
+        graph.add_terminal(1, 0, 4);
+        graph.add_edge(0, 1, 2, 0);
+        assert_eq!(2, graph.maxflow());
+        assert!(graph.is_source(0));
+        assert!(!graph.is_source(1));
+    }
+
+    #[test]
+    fn maxflow_diamond() {
+        let mut graph = Graph::new(4);
+        graph.add_terminal(0, 10, 0);
+        graph.add_terminal(3, 0, 10);
+        graph.add_edge(0, 1, 3, 0);
+        graph.add_edge(0, 2, 4, 0);
+        graph.add_edge(1, 2, 2, 0);
+        graph.add_edge(1, 3, 2, 0);
+        graph.add_edge(2, 3, 5, 0);
+        assert_eq!(7, graph.maxflow());
+        assert!(graph.is_source(0));
+        assert!(!graph.is_source(1));
+        assert!(!graph.is_source(2));
+        assert!(!graph.is_source(3));
+    }
+
+    #[test]
+    fn maxflow_terminal_only() {
+        let mut graph = Graph::new(2);
+        graph.add_terminal(0, 3, 5);
+        graph.add_terminal(1, 7, 1);
+        assert_eq!(4, graph.maxflow());
+        assert!(!graph.is_source(0));
+        assert!(graph.is_source(1));
+    }
+
+    #[test]
+    fn maxflow_grid() {
+        // every column of a 3x3 grid is crossed once, the cheapest one carrying the flow
+        let mut graph = Graph::new(9);
+        for y in 0..3 {
+            graph.add_terminal(3 * y, INFINITE, 0);
+            graph.add_terminal(3 * y + 2, 0, INFINITE);
+            graph.add_edge(3 * y, 3 * y + 1, 4 + y as u64, 0);
+            graph.add_edge(3 * y + 1, 3 * y + 2, 1, 0);
+        }
+        for x in 0..3 {
+            graph.add_edge(x, x + 3, 1, 1);
+            graph.add_edge(x + 3, x + 6, 1, 1);
+        }
+        assert_eq!(3, graph.maxflow());
+        for y in 0..3 {
+            assert!(graph.is_source(3 * y + 1));
+        }
+    }
+}
@@ -0,0 +1,327 @@
+use std::convert::TryInto;
+use std::error::Error;
+
+use image::DynamicImage;
+use img_parts::jpeg::{markers, JpegSegment};
+use img_parts::png::PngChunk;
+use img_parts::{Bytes, DynImage, ImageEXIF, ImageICC};
+
+const XMP_JPEG_PREFIX: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const XMP_PNG_KEYWORD: &[u8] = b"XML:com.adobe.xmp\0";
+const CHUNK_ITXT: [u8; 4] = *b"iTXt";
+
+const ORIENTATION: u16 = 0x0112;
+const EXIF_IFD: u16 = 0x8769;
+const PIXEL_X_DIMENSION: u16 = 0xa002;
+const PIXEL_Y_DIMENSION: u16 = 0xa003;
+const SHORT: u16 = 3;
+const LONG: u16 = 4;
+
+// metadata carried from the input file to the carved one, for the containers img-parts can
+// edit (jpeg, png and webp); exif is kept as raw tiff data and xmp as the raw packet
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Metadata {
+    pub icc_profile: Option<Vec<u8>>,
+    pub exif: Option<Vec<u8>>,
+    pub xmp: Option<Vec<u8>>,
+}
+
+impl Metadata {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let img = match DynImage::from_bytes(Bytes::copy_from_slice(bytes))? {
+            Some(img) => img,
+            None => return Ok(Self::default()),
+        };
+        let xmp = match &img {
+            DynImage::Jpeg(jpeg) => jpeg
+                .segments_by_marker(markers::APP1)
+                .find_map(|segment| segment.contents().strip_prefix(XMP_JPEG_PREFIX))
+                .map(|xmp| xmp.to_vec()),
+            DynImage::Png(png) => png
+                .chunks_by_type(CHUNK_ITXT)
+                .find_map(|chunk| xmp_from_itxt(chunk.contents()))
+                .map(|xmp| xmp.to_vec()),
+            DynImage::WebP(_) => None,
+        };
+        Ok(Self {
+            icc_profile: img.icc_profile().map(|icc| icc.to_vec()),
+            exif: img.exif().map(|exif| exif.to_vec()),
+            xmp,
+        })
+    }
+
+    pub fn orientation(&self) -> u16 {
+        self.exif
+            .as_deref()
+            .and_then(|exif| {
+                let (entry, big_endian) = find_tag(exif, ORIENTATION)?;
+                read_u16(exif, entry + 8, big_endian)
+            })
+            .filter(|orientation| (1..=8).contains(orientation))
+            .unwrap_or(1)
+    }
+
+    // writes the metadata into an encoded image of the given dimensions, the exif orientation
+    // being reset since the pixels are stored upright; other containers are returned as is
+    pub fn embed(
+        &self,
+        encoded: Vec<u8>,
+        dimensions: (u32, u32),
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut img = match DynImage::from_bytes(Bytes::from(encoded.clone()))? {
+            Some(img) => img,
+            None => return Ok(encoded),
+        };
+        img.set_icc_profile(self.icc_profile.clone().map(Bytes::from));
+        img.set_exif(self.exif.as_ref().map(|exif| {
+            let mut exif = exif.clone();
+            set_tag(&mut exif, ORIENTATION, 1);
+            set_tag(&mut exif, PIXEL_X_DIMENSION, dimensions.0);
+            set_tag(&mut exif, PIXEL_Y_DIMENSION, dimensions.1);
+            Bytes::from(exif)
+        }));
+        if let Some(xmp) = &self.xmp {
+            match &mut img {
+                DynImage::Jpeg(jpeg) => {
+                    let segments = jpeg.segments_mut();
+                    // app segments come first, right after the start of image
+                    let index = segments
+                        .iter()
+                        .position(|segment| {
+                            !(markers::APP0..=markers::APP15).contains(&segment.marker())
+                        })
+                        .unwrap_or(segments.len());
+                    let contents = [XMP_JPEG_PREFIX, xmp].concat();
+                    segments.insert(
+                        index,
+                        JpegSegment::new_with_contents(markers::APP1, Bytes::from(contents)),
+                    );
+                }
+                DynImage::Png(png) => {
+                    let chunks = png.chunks_mut();
+                    // keyword, uncompressed, no language tag nor translated keyword
+                    let contents = [XMP_PNG_KEYWORD, b"\0\0\0\0", xmp].concat();
+                    chunks.insert(
+                        chunks.len() - 1,
+                        PngChunk::new(CHUNK_ITXT, Bytes::from(contents)),
+                    );
+                }
+                DynImage::WebP(_) => {}
+            }
+        }
+        Ok(img.encoder().bytes().to_vec())
+    }
+}
+
+// turns the image so that it is displayed as the exif orientation says
+pub fn apply_orientation(img: DynamicImage, orientation: u16) -> DynamicImage {
+    match orientation {
+        2 => img.fliph(),
+        3 => img.rotate180(),
+        4 => img.flipv(),
+        5 => img.rotate90().fliph(),
+        6 => img.rotate90(),
+        7 => img.rotate270().fliph(),
+        8 => img.rotate270(),
+        _ => img,
+    }
+}
+
+fn xmp_from_itxt(contents: &[u8]) -> Option<&[u8]> {
+    let rest = contents.strip_prefix(XMP_PNG_KEYWORD)?;
+    // compressed packets are not supported
+    let rest = rest.strip_prefix(b"\0\0")?;
+    let language_end = rest.iter().position(|&b| b == 0)?;
+    let rest = &rest[language_end + 1..];
+    let keyword_end = rest.iter().position(|&b| b == 0)?;
+    Some(&rest[keyword_end + 1..])
+}
+
+// offset of the 12 bytes entry of a tag, looked up in the first image directory and then in
+// the exif one, along with the byte order of the tiff data
+fn find_tag(exif: &[u8], tag: u16) -> Option<(usize, bool)> {
+    let big_endian = match exif.get(..4)? {
+        b"MM\0*" => true,
+        b"II*\0" => false,
+        _ => return None,
+    };
+    let ifd = read_u32(exif, 4, big_endian)? as usize;
+    if let Some(entry) = find_entry(exif, ifd, tag, big_endian) {
+        return Some((entry, big_endian));
+    }
+    let exif_ifd = find_entry(exif, ifd, EXIF_IFD, big_endian)?;
+    let exif_ifd = read_u32(exif, exif_ifd + 8, big_endian)? as usize;
+    find_entry(exif, exif_ifd, tag, big_endian).map(|entry| (entry, big_endian))
+}
+
+fn find_entry(exif: &[u8], ifd: usize, tag: u16, big_endian: bool) -> Option<usize> {
+    let count = read_u16(exif, ifd, big_endian)? as usize;
+    (0..count)
+        .map(|i| ifd + 2 + 12 * i)
+        .find(|&entry| read_u16(exif, entry, big_endian) == Some(tag))
+}
+
+fn set_tag(exif: &mut [u8], tag: u16, value: u32) {
+    let (entry, big_endian) = match find_tag(exif, tag) {
+        Some(found) => found,
+        None => return,
+    };
+    let bytes = match (read_u16(exif, entry + 2, big_endian), big_endian) {
+        (Some(SHORT), true) => (value as u16).to_be_bytes().to_vec(),
+        (Some(SHORT), false) => (value as u16).to_le_bytes().to_vec(),
+        (Some(LONG), true) => value.to_be_bytes().to_vec(),
+        (Some(LONG), false) => value.to_le_bytes().to_vec(),
+        _ => return,
+    };
+    if let Some(target) = exif.get_mut(entry + 8..entry + 8 + bytes.len()) {
+        target.copy_from_slice(&bytes);
+    }
+}
+
+fn read_u16(exif: &[u8], offset: usize, big_endian: bool) -> Option<u16> {
+    let bytes = exif.get(offset..offset + 2)?.try_into().ok()?;
+    Some(match big_endian {
+        true => u16::from_be_bytes(bytes),
+        false => u16::from_le_bytes(bytes),
+    })
+}
+
+fn read_u32(exif: &[u8], offset: usize, big_endian: bool) -> Option<u32> {
+    let bytes = exif.get(offset..offset + 4)?.try_into().ok()?;
+    Some(match big_endian {
+        true => u32::from_be_bytes(bytes),
+        false => u32::from_le_bytes(bytes),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageOutputFormat, Rgb, RgbImage};
+    use std::io::Cursor;
+
+    // little endian tiff data with an orientation in the first directory and the pixel
+    // dimensions in the exif one
+    fn exif(orientation: u16) -> Vec<u8> {
+        let mut exif = b"II*\0".to_vec();
+        exif.extend_from_slice(&8u32.to_le_bytes());
+        exif.extend_from_slice(&2u16.to_le_bytes());
+        for (tag, kind, value) in [
+            (ORIENTATION, SHORT, orientation as u32),
+            (EXIF_IFD, LONG, 38),
+        ] {
+            exif.extend_from_slice(&tag.to_le_bytes());
+            exif.extend_from_slice(&kind.to_le_bytes());
+            exif.extend_from_slice(&1u32.to_le_bytes());
+            exif.extend_from_slice(&value.to_le_bytes());
+        }
+        exif.extend_from_slice(&0u32.to_le_bytes());
+        exif.extend_from_slice(&2u16.to_le_bytes());
+        for tag in [PIXEL_X_DIMENSION, PIXEL_Y_DIMENSION] {
+            exif.extend_from_slice(&tag.to_le_bytes());
+            exif.extend_from_slice(&LONG.to_le_bytes());
+            exif.extend_from_slice(&1u32.to_le_bytes());
+            exif.extend_from_slice(&100u32.to_le_bytes());
+        }
+        exif.extend_from_slice(&0u32.to_le_bytes());
+        exif
+    }
+
+    fn encode(img: &RgbImage, format: ImageOutputFormat) -> Vec<u8> {
+        let mut bytes = Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(img.clone())
+            .write_to(&mut bytes, format)
+            .unwrap();
+        bytes.into_inner()
+    }
+
+    #[test]
+    fn orientation() {
+        for orientation in 1..=8 {
+            let metadata = Metadata {
+                exif: Some(exif(orientation)),
+                ..Metadata::default()
+            };
+            assert_eq!(orientation, metadata.orientation());
+        }
+        let metadata = Metadata {
+            exif: Some(exif(9)),
+            ..Metadata::default()
+        };
+        assert_eq!(1, metadata.orientation());
+        assert_eq!(1, Metadata::default().orientation());
+    }
+
+    #[test]
+    fn orientation_applied() {
+        let img = RgbImage::from_fn(3, 2, |x, y| Rgb([x as u8, y as u8, 0]));
+        for orientation in 1..=8 {
+            let turned = apply_orientation(DynamicImage::ImageRgb8(img.clone()), orientation);
+            let turned = turned.to_rgb8();
+            for (x, y, pixel) in img.enumerate_pixels() {
+                // where each stored pixel ends up once displayed
+                let (turned_x, turned_y) = match orientation {
+                    2 => (2 - x, y),
+                    3 => (2 - x, 1 - y),
+                    4 => (x, 1 - y),
+                    5 => (y, x),
+                    6 => (1 - y, x),
+                    7 => (1 - y, 2 - x),
+                    8 => (y, 2 - x),
+                    _ => (x, y),
+                };
+                assert_eq!(pixel, turned.get_pixel(turned_x, turned_y));
+            }
+        }
+    }
+
+    #[test]
+    fn metadata_round_trip() {
+        let img = RgbImage::from_fn(4, 3, |x, y| Rgb([x as u8 * 60, y as u8 * 80, 0]));
+        let metadata = Metadata {
+            icc_profile: Some(b"not quite an icc profile".to_vec()),
+            exif: Some(exif(6)),
+            xmp: Some(b"<x:xmpmeta/>".to_vec()),
+        };
+        for format in [ImageOutputFormat::Png, ImageOutputFormat::Jpeg(90)] {
+            let encoded = metadata.embed(encode(&img, format), (4, 3)).unwrap();
+            assert_eq!(
+                (4, 3),
+                image::load_from_memory(&encoded)
+                    .unwrap()
+                    .to_rgb8()
+                    .dimensions()
+            );
+            let embedded = Metadata::from_bytes(&encoded).unwrap();
+            assert_eq!(metadata.icc_profile, embedded.icc_profile);
+            assert_eq!(metadata.xmp, embedded.xmp);
+            assert_eq!(1, embedded.orientation());
+            let exif = embedded.exif.unwrap();
+            let (entry, _) = find_tag(&exif, PIXEL_X_DIMENSION).unwrap();
+            assert_eq!(Some(4), read_u32(&exif, entry + 8, false));
+            let (entry, _) = find_tag(&exif, PIXEL_Y_DIMENSION).unwrap();
+            assert_eq!(Some(3), read_u32(&exif, entry + 8, false));
+        }
+    }
+
+    #[test]
+    fn metadata_unsupported() {
+        let img = RgbImage::new(2, 2);
+        let encoded = encode(&img, ImageOutputFormat::Bmp);
+        assert_eq!(Metadata::default(), Metadata::from_bytes(&encoded).unwrap());
+        let metadata = Metadata {
+            exif: Some(exif(3)),
+            ..Metadata::default()
+        };
+        assert_eq!(encoded, metadata.embed(encoded.clone(), (2, 2)).unwrap());
+    }
+
+    #[test]
+    fn metadata_unreadable() {
+        // a bad IEND checksum is ignored by the decoder but rejected by img-parts
+        let mut encoded = encode(&RgbImage::new(2, 2), ImageOutputFormat::Png);
+        *encoded.last_mut().unwrap() ^= 0xff;
+        assert!(image::load_from_memory(&encoded).is_ok());
+        assert!(Metadata::from_bytes(&encoded).is_err());
+    }
+}
@@ -1,37 +1,322 @@
+use std::collections::hash_map::{Entry, HashMap};
 use std::error::Error;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::thread;
+
+use glob::Pattern;
+use image::ImageFormat;
+
+use crate::aspect::{Aspect, AspectPolicy};
+use crate::encode::{self, EncodeOptions};
+
+const USAGE: &str =
+    "Usage: rsc [--outdir DIR] [--glob PATTERN] [--jobs N] [--sequence] [--strip-metadata] \
+                     [--format png|jpeg|webp|tiff|bmp] [--quality Q] [--compression fast|default|best] \
+                     (/path/to/img... | -) (new_width new_height | --aspect W:H [--aspect-policy remove|insert])";
+
+// stands for stdin as an input, and for stdout as the matching output
+pub const STDIO: &str = "-";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Dimension {
+    Absolute(u32),
+    Percent(u32),
+}
+
+impl Dimension {
+    pub fn resolve(&self, original: u32) -> u32 {
+        match *self {
+            Dimension::Absolute(value) => value,
+            Dimension::Percent(percent) => {
+                ((original as u64 * percent as u64 + 50) / 100).max(1) as u32
+            }
+        }
+    }
+}
+
+impl FromStr for Dimension {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_suffix('%') {
+            Some(percent) => match percent.parse()? {
+                0 => Err(format!("percentage must be positive, got {}", s).into()),
+                percent => Ok(Dimension::Percent(percent)),
+            },
+            None => Ok(Dimension::Absolute(s.parse()?)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Size {
+    Dimensions(Dimension, Dimension),
+    Aspect(Aspect, AspectPolicy),
+}
+
+impl Size {
+    pub fn resolve(&self, width: u32, height: u32) -> Result<(u32, u32), Box<dyn Error>> {
+        match self {
+            Size::Dimensions(new_width, new_height) => {
+                Ok((new_width.resolve(width), new_height.resolve(height)))
+            }
+            Size::Aspect(aspect, policy) => aspect.target_dimensions(width, height, *policy),
+        }
+    }
+}
 
 #[derive(Debug, Eq, PartialEq)]
 pub struct Config {
-    pub infile: PathBuf,
-    pub new_width: u32,
-    pub new_height: u32,
+    pub inputs: Vec<PathBuf>,
+    pub size: Size,
+    pub outdir: Option<PathBuf>,
+    pub glob: Option<String>,
+    pub jobs: usize,
+    pub sequence: bool,
+    pub strip_metadata: bool,
+    pub format: Option<ImageFormat>,
+    pub encode: EncodeOptions,
 }
 
 impl Config {
     pub fn new(args: &[String]) -> Result<Self, Box<dyn Error>> {
-        if args.len() < 4 {
-            Err("Usage: rsc /path/to/img new_width new_height".into())
-        } else {
-            let infile: PathBuf = args[1].parse()?;
-            let new_width: u32 = args[2].parse()?;
-            let new_height: u32 = args[3].parse()?;
-
-            Ok(Self {
-                infile,
-                new_width,
-                new_height,
+        let mut positional = Vec::new();
+        let (mut outdir, mut glob, mut jobs) = (None, None, None);
+        let (mut aspect, mut policy) = (None, None);
+        let (mut sequence, mut strip_metadata) = (false, false);
+        let (mut format, mut encode) = (None, EncodeOptions::default());
+        let mut args_iter = args.iter().skip(1);
+        while let Some(arg) = args_iter.next() {
+            match arg.as_str() {
+                "-o" | "--outdir" => outdir = Some(next_value(&mut args_iter, arg)?.parse()?),
+                "-g" | "--glob" => glob = Some(next_value(&mut args_iter, arg)?.clone()),
+                "-j" | "--jobs" => jobs = Some(next_value(&mut args_iter, arg)?.parse()?),
+                "-a" | "--aspect" => aspect = Some(next_value(&mut args_iter, arg)?.parse()?),
+                "--aspect-policy" => policy = Some(next_value(&mut args_iter, arg)?.parse()?),
+                "-s" | "--sequence" => sequence = true,
+                "--strip-metadata" => strip_metadata = true,
+                "-f" | "--format" => {
+                    format = Some(encode::parse_format(next_value(&mut args_iter, arg)?)?)
+                }
+                "-q" | "--quality" => {
+                    encode.quality = encode::parse_quality(next_value(&mut args_iter, arg)?)?
+                }
+                "--compression" => {
+                    encode.compression =
+                        encode::parse_compression(next_value(&mut args_iter, arg)?)?
+                }
+                _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg).into()),
+                _ => positional.push(arg),
+            }
+        }
+
+        if let (Some(_), [.., new_width, new_height]) = (aspect, positional.as_slice()) {
+            if new_width.parse::<Dimension>().is_ok() && new_height.parse::<Dimension>().is_ok() {
+                return Err(format!(
+                    "--aspect replaces the new dimensions, got {} {}\n{}",
+                    new_width, new_height, USAGE
+                )
+                .into());
+            }
+        }
+        let size = match aspect {
+            Some(aspect) if !positional.is_empty() => {
+                Size::Aspect(aspect, policy.unwrap_or_default())
+            }
+            None if positional.len() >= 3 && policy.is_none() => {
+                let new_height = positional.pop().unwrap().parse()?;
+                let new_width = positional.pop().unwrap().parse()?;
+                Size::Dimensions(new_width, new_height)
+            }
+            _ => return Err(USAGE.into()),
+        };
+        let mut inputs: Vec<PathBuf> = Vec::with_capacity(positional.len());
+        for input in positional {
+            inputs.push(input.parse()?);
+        }
+        let stdin = inputs.iter().any(|input| input == Path::new(STDIO));
+        if stdin && (inputs.len() > 1 || outdir.is_some() || sequence) {
+            return Err("stdin must be the only input, without --outdir nor --sequence".into());
+        }
+        if let Some(pattern) = &glob {
+            Pattern::new(pattern)?;
+        }
+        let jobs = match jobs {
+            Some(0) => return Err("jobs must be positive, got 0".into()),
+            Some(jobs) => jobs,
+            None => thread::available_parallelism().map_or(1, |n| n.get()),
+        };
+
+        Ok(Self {
+            inputs,
+            size,
+            outdir,
+            glob,
+            jobs,
+            sequence,
+            strip_metadata,
+            format,
+            encode,
+        })
+    }
+
+    pub fn is_batch(&self) -> bool {
+        !self.sequence
+            && (self.inputs.len() > 1
+                || self.outdir.is_some()
+                || self.inputs.iter().any(|p| p.is_dir()))
+    }
+
+    pub fn get_infiles(&self) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let pattern = Pattern::new(self.glob.as_deref().unwrap_or("*"))?;
+        let mut infiles = Vec::new();
+        for input in &self.inputs {
+            if input.is_dir() {
+                let mut entries = list_images(input, &pattern)?;
+                entries.sort();
+                infiles.append(&mut entries);
+            } else {
+                infiles.push(input.clone());
+            }
+        }
+        Ok(infiles)
+    }
+
+    pub fn get_frames(&self, dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        if !dir.is_dir() {
+            return Err(format!(
+                "sequence mode expects directories of frames, got {}",
+                dir.display()
+            )
+            .into());
+        }
+        let pattern = Pattern::new(self.glob.as_deref().unwrap_or("*"))?;
+        let mut frames = list_images(dir, &pattern)?;
+        frames.sort_by_cached_key(|path| (frame_number(path), path.clone()));
+        Ok(frames)
+    }
+
+    pub fn get_sequence_outdir(&self, dir: &Path) -> Result<PathBuf, Box<dyn Error>> {
+        // `.` and `..` have no name of their own, that of the directory they stand for is used
+        let named = match dir.file_name() {
+            Some(_) => dir.to_path_buf(),
+            None => dir.canonicalize()?,
+        };
+        let name = named
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| {
+                format!(
+                    "cannot derive an output directory name from {}",
+                    dir.display()
+                )
+            })?;
+        Ok(match &self.outdir {
+            Some(outdir) => outdir.join(name),
+            None => named.with_file_name(format!("{}_carved", name)),
+        })
+    }
+
+    pub fn get_outfile(&self, infile: &Path) -> Result<PathBuf, Box<dyn Error>> {
+        if infile == Path::new(STDIO) {
+            return Ok(infile.to_path_buf());
+        }
+        let invalid = || {
+            format!(
+                "cannot derive an output file name from {}",
+                infile.display()
+            )
+        };
+        let stem = infile
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(invalid)?;
+        let extension = match self.format {
+            Some(format) => format.extensions_str()[0],
+            None => infile
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .ok_or("cannot infer output format, pass --format")?,
+        };
+        Ok(match &self.outdir {
+            Some(outdir) => outdir.join(format!("{}.{}", stem, extension)),
+            None => infile.with_file_name(format!("{}_carved.{}", stem, extension)),
+        })
+    }
+
+    // outfiles of a batch, an input whose outfile is taken by an earlier one failing instead of
+    // overwriting it
+    pub fn get_outfiles(&self, infiles: &[PathBuf]) -> Vec<Result<PathBuf, String>> {
+        let mut taken: HashMap<PathBuf, &PathBuf> = HashMap::new();
+        infiles
+            .iter()
+            .map(|infile| {
+                let outfile = self.get_outfile(infile).map_err(|e| e.to_string())?;
+                match taken.entry(outfile.clone()) {
+                    Entry::Occupied(entry) => Err(format!(
+                        "output {} already written for {}",
+                        outfile.display(),
+                        entry.get().display()
+                    )),
+                    Entry::Vacant(entry) => {
+                        entry.insert(infile);
+                        Ok(outfile)
+                    }
+                }
             })
+            .collect()
+    }
+
+    // --format, then the output extension, then the format the input was decoded from
+    pub fn get_format(
+        &self,
+        outfile: &Path,
+        input_format: Option<ImageFormat>,
+    ) -> Result<ImageFormat, Box<dyn Error>> {
+        let format = match self.format {
+            Some(format) => format,
+            None if outfile == Path::new(STDIO) => {
+                input_format.ok_or("cannot guess the output format, use --format")?
+            }
+            None => ImageFormat::from_path(outfile)?,
+        };
+        if !encode::OUTPUT_FORMATS.contains(&format) {
+            return Err(format!("unsupported output format, got {:?}", format).into());
         }
+        Ok(format)
     }
+}
 
-    pub fn get_outfile(&self) -> PathBuf {
-        self.infile.with_file_name(format!(
-            "{}_carved.{}",
-            self.infile.file_stem().unwrap().to_str().unwrap(),
-            self.infile.extension().unwrap().to_str().unwrap()
-        ))
+fn list_images(dir: &Path, pattern: &Pattern) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut images = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let matches = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| pattern.matches(name));
+        if path.is_file() && matches && ImageFormat::from_path(&path).is_ok() {
+            images.push(path);
+        }
     }
+    Ok(images)
+}
+
+// trailing digits of the file stem, so that frame_10 comes after frame_9
+fn frame_number(path: &Path) -> Option<u64> {
+    let stem = path.file_stem()?.to_str()?;
+    let digits = stem.len() - stem.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    stem[stem.len() - digits..].parse().ok()
+}
+
+fn next_value<'a, I: Iterator<Item = &'a String>>(
+    args: &mut I,
+    option: &str,
+) -> Result<&'a String, Box<dyn Error>> {
+    args.next()
+        .ok_or_else(|| format!("missing value for option {}", option).into())
 }
 
 #[cfg(test)]
@@ -57,11 +342,142 @@ mod tests {
             String::from("100"),
         ];
         assert_eq!(
-            Err(String::from("Usage: rsc /path/to/img new_width new_height")),
+            Err(String::from(USAGE)),
+            Config::new(&args).map_err(|e| format!("{}", e))
+        );
+    }
+
+    #[test]
+    fn config_batch() {
+        let args = [
+            String::from("rsc"),
+            String::from("--outdir"),
+            String::from("./out"),
+            String::from("-j"),
+            String::from("3"),
+            String::from("--glob"),
+            String::from("*.jpg"),
+            String::from("--strip-metadata"),
+            String::from("./img/a.png"),
+            String::from("./img/b.png"),
+            String::from("50%"),
+            String::from("42"),
+        ];
+        assert_eq!(
+            Config {
+                inputs: vec![PathBuf::from("./img/a.png"), PathBuf::from("./img/b.png")],
+                size: Size::Dimensions(Dimension::Percent(50), Dimension::Absolute(42)),
+                outdir: Some(PathBuf::from("./out")),
+                glob: Some(String::from("*.jpg")),
+                jobs: 3,
+                sequence: false,
+                strip_metadata: true,
+                format: None,
+                encode: EncodeOptions::default(),
+            },
+            Config::new(&args).unwrap()
+        );
+    }
+
+    #[test]
+    fn config_aspect() {
+        let args = [
+            String::from("rsc"),
+            String::from("--aspect"),
+            String::from("16:9"),
+            String::from("--aspect-policy"),
+            String::from("insert"),
+            String::from("./img/example_path.png"),
+        ];
+        let config = Config::new(&args).unwrap();
+        assert_eq!(vec![PathBuf::from("./img/example_path.png")], config.inputs);
+        assert_eq!(
+            Size::Aspect(Aspect::new(16, 9).unwrap(), AspectPolicy::Insert),
+            config.size
+        );
+        assert_eq!((1721, 968), config.size.resolve(1428, 968).unwrap());
+    }
+
+    #[test]
+    fn config_aspect_error() {
+        let args = [
+            String::from("rsc"),
+            String::from("--aspect-policy"),
+            String::from("insert"),
+            String::from("./img/example_path.png"),
+            String::from("100"),
+            String::from("42"),
+        ];
+        assert_eq!(
+            Err(String::from(USAGE)),
+            Config::new(&args).map_err(|e| format!("{}", e))
+        );
+    }
+
+    #[test]
+    fn config_aspect_dimensions() {
+        let args = [
+            String::from("rsc"),
+            String::from("--aspect"),
+            String::from("16:9"),
+            String::from("./img/example_path.png"),
+            String::from("50%"),
+            String::from("42"),
+        ];
+        assert_eq!(
+            Err(format!(
+                "--aspect replaces the new dimensions, got 50% 42\n{}",
+                USAGE
+            )),
+            Config::new(&args).map_err(|e| format!("{}", e))
+        );
+    }
+
+    #[test]
+    fn config_unknown_option() {
+        let args = [
+            String::from("rsc"),
+            String::from("--fast"),
+            String::from("./img/example_path.png"),
+            String::from("100"),
+            String::from("42"),
+        ];
+        assert_eq!(
+            Err(String::from("unknown option --fast")),
             Config::new(&args).map_err(|e| format!("{}", e))
         );
     }
 
+    #[test]
+    fn dimension_resolve() {
+        assert_eq!(100, "100".parse::<Dimension>().unwrap().resolve(1428));
+        assert_eq!(714, "50%".parse::<Dimension>().unwrap().resolve(1428));
+        assert_eq!(1, "1%".parse::<Dimension>().unwrap().resolve(20));
+        assert!("0%".parse::<Dimension>().is_err());
+    }
+
+    #[test]
+    fn infiles() {
+        let args = [
+            String::from("rsc"),
+            String::from("--glob"),
+            String::from("Broadway_tower_edit.*"),
+            String::from("./img"),
+            String::from("./img/example_path.png"),
+            String::from("100"),
+            String::from("42"),
+        ];
+        let config = Config::new(&args).unwrap();
+        assert!(config.is_batch());
+        assert_eq!(
+            vec![
+                PathBuf::from("./img/Broadway_tower_edit.jpg"),
+                PathBuf::from("./img/example_path.png")
+            ],
+            config.get_infiles().unwrap()
+        );
+    }
+
     #[test]
     fn outfile() {
         let args = [
@@ -71,9 +487,204 @@ mod tests {
             String::from("42"),
         ];
         let config = Config::new(&args).unwrap();
+        assert!(!config.is_batch());
         assert_eq!(
             PathBuf::from("./img/example_path_carved.png"),
-            config.get_outfile()
+            config.get_outfile(&config.inputs[0]).unwrap()
+        );
+    }
+
+    #[test]
+    fn outfile_outdir() {
+        let args = [
+            String::from("rsc"),
+            String::from("-o"),
+            String::from("./out"),
+            String::from("./img/example_path.png"),
+            String::from("100"),
+            String::from("42"),
+        ];
+        let config = Config::new(&args).unwrap();
+        assert_eq!(
+            PathBuf::from("./out/example_path.png"),
+            config.get_outfile(&config.inputs[0]).unwrap()
+        );
+    }
+
+    #[test]
+    fn outfile_invalid() {
+        let args = [
+            String::from("rsc"),
+            String::from("-o"),
+            String::from("./out"),
+            String::from("./img"),
+            String::from("./other/Broadway_tower_edit.jpg"),
+            String::from("100"),
+            String::from("42"),
+        ];
+        let config = Config::new(&args).unwrap();
+        let infiles = [
+            PathBuf::from("./img/Broadway_tower_edit.jpg"),
+            PathBuf::from("./img/noext"),
+            PathBuf::from("./other/Broadway_tower_edit.jpg"),
+        ];
+        assert_eq!(
+            vec![
+                Ok(PathBuf::from("./out/Broadway_tower_edit.jpg")),
+                Err(String::from("cannot infer output format, pass --format")),
+                Err(String::from(
+                    "output ./out/Broadway_tower_edit.jpg already written for ./img/Broadway_tower_edit.jpg"
+                )),
+            ],
+            config.get_outfiles(&infiles)
+        );
+    }
+
+    #[test]
+    fn config_sequence() {
+        let args = [
+            String::from("rsc"),
+            String::from("--sequence"),
+            String::from("-o"),
+            String::from("./out"),
+            String::from("./img"),
+            String::from("50%"),
+            String::from("100%"),
+        ];
+        let config = Config::new(&args).unwrap();
+        assert!(config.sequence);
+        assert!(!config.is_batch());
+        assert_eq!(
+            PathBuf::from("./out/img"),
+            config.get_sequence_outdir(&config.inputs[0]).unwrap()
+        );
+        assert_eq!(
+            PathBuf::from("./out/img"),
+            config.get_sequence_outdir(Path::new("./img/.")).unwrap()
+        );
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(
+            Path::new("./out").join(cwd.file_name().unwrap()),
+            config.get_sequence_outdir(Path::new("./img/..")).unwrap()
+        );
+        assert_eq!(
+            Err(String::from(
+                "cannot derive an output directory name from /"
+            )),
+            config
+                .get_sequence_outdir(Path::new("/"))
+                .map_err(|e| format!("{}", e))
+        );
+        assert_eq!(
+            Err(String::from(
+                "sequence mode expects directories of frames, got ./img/example_path.png"
+            )),
+            config
+                .get_frames(Path::new("./img/example_path.png"))
+                .map_err(|e| format!("{}", e))
+        );
+    }
+
+    #[test]
+    fn frame_order() {
+        let mut frames = vec![
+            PathBuf::from("clip/frame_10.png"),
+            PathBuf::from("clip/frame_9.png"),
+            PathBuf::from("clip/frame_100.png"),
+            PathBuf::from("clip/frame_1.png"),
+        ];
+        frames.sort_by_cached_key(|path| (frame_number(path), path.clone()));
+        assert_eq!(
+            vec![
+                PathBuf::from("clip/frame_1.png"),
+                PathBuf::from("clip/frame_9.png"),
+                PathBuf::from("clip/frame_10.png"),
+                PathBuf::from("clip/frame_100.png"),
+            ],
+            frames
+        );
+        assert_eq!(None, frame_number(Path::new("clip/poster.png")));
+        assert_eq!(
+            PathBuf::from("clip_carved"),
+            Config::new(&[
+                String::from("rsc"),
+                String::from("-s"),
+                String::from("clip"),
+                String::from("10"),
+                String::from("10"),
+            ])
+            .unwrap()
+            .get_sequence_outdir(Path::new("clip"))
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn config_format() {
+        let args = [
+            String::from("rsc"),
+            String::from("--format"),
+            String::from("webp"),
+            String::from("-q"),
+            String::from("90"),
+            String::from("--compression"),
+            String::from("best"),
+            String::from("./img/example_path.png"),
+            String::from("100"),
+            String::from("42"),
+        ];
+        let config = Config::new(&args).unwrap();
+        assert_eq!(Some(ImageFormat::WebP), config.format);
+        assert_eq!(90, config.encode.quality);
+        let outfile = config.get_outfile(&config.inputs[0]).unwrap();
+        assert_eq!(PathBuf::from("./img/example_path_carved.webp"), outfile);
+        assert_eq!(
+            PathBuf::from("./img/noext_carved.webp"),
+            config.get_outfile(Path::new("./img/noext")).unwrap()
+        );
+        assert_eq!(
+            ImageFormat::WebP,
+            config.get_format(&outfile, None).unwrap()
+        );
+    }
+
+    #[test]
+    fn config_stdio() {
+        let args = [
+            String::from("rsc"),
+            String::from("-"),
+            String::from("50%"),
+            String::from("42"),
+        ];
+        let config = Config::new(&args).unwrap();
+        assert!(!config.is_batch());
+        let outfile = config.get_outfile(&config.inputs[0]).unwrap();
+        assert_eq!(PathBuf::from("-"), outfile);
+        assert_eq!(
+            ImageFormat::Jpeg,
+            config
+                .get_format(&outfile, Some(ImageFormat::Jpeg))
+                .unwrap()
+        );
+        assert_eq!(
+            Err(String::from("unsupported output format, got Gif")),
+            config
+                .get_format(&outfile, Some(ImageFormat::Gif))
+                .map_err(|e| format!("{}", e))
+        );
+        let args = [
+            String::from("rsc"),
+            String::from("-o"),
+            String::from("./out"),
+            String::from("-"),
+            String::from("50%"),
+            String::from("42"),
+        ];
+        assert_eq!(
+            Err(String::from(
+                "stdin must be the only input, without --outdir nor --sequence"
+            )),
+            Config::new(&args).map_err(|e| format!("{}", e))
         );
     }
 }
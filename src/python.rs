@@ -0,0 +1,254 @@
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::error::Error;
+
+use numpy::ndarray::{Array2, Array3};
+use numpy::{
+    Element, IntoPyArray, PyArray1, PyArray2, PyArray3, PyReadonlyArray2, PyReadonlyArray3,
+    PyUntypedArrayMethods,
+};
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::array::{self, Array2d};
+use crate::carver::Options;
+use crate::energy;
+use crate::raw::{self, Layout, RawFrame};
+use crate::seam::{self, TieBreak};
+
+const ENERGY_OPTIONS: [&str; 4] = ["energy", "border", "protect", "threads"];
+const SEAM_OPTIONS: [&str; 5] = [
+    "connectivity",
+    "strip",
+    "tie_break",
+    "seed",
+    "diagonal_penalty",
+];
+const CARVE_OPTIONS: [&str; 3] = ["seams_per_pass", "proximity_penalty", "order"];
+
+#[pymodule]
+fn rsc(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(seamcarve_array, m)?)?;
+    m.add_function(wrap_pyfunction!(energy_array, m)?)?;
+    m.add_function(wrap_pyfunction!(find_vertical_seam_array, m)?)?;
+    m.add_function(wrap_pyfunction!(find_horizontal_seam_array, m)?)?;
+    Ok(())
+}
+
+// takes a HxWx3 or HxWx4 uint8 array, the alpha channel following the seams found on the colors
+#[pyfunction]
+#[pyo3(name = "seamcarve", signature = (img, new_width, new_height, **options))]
+fn seamcarve_array<'py>(
+    py: Python<'py>,
+    img: PyReadonlyArray3<'py, u8>,
+    new_width: u32,
+    new_height: u32,
+    options: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Bound<'py, PyArray3<u8>>> {
+    let allowed = [&ENERGY_OPTIONS[..], &SEAM_OPTIONS, &CARVE_OPTIONS].concat();
+    let options = parse_options(options, &allowed)?;
+    let (data, width, height, layout) = pixels_from_array(&img)?;
+    let frame = RawFrame::packed(&data, width, height, layout).map_err(value_error)?;
+    let carved = py
+        .detach(|| {
+            raw::seamcarve_raw(&frame, new_width, new_height, &options).map_err(|e| e.to_string())
+        })
+        .map_err(PyValueError::new_err)?;
+    // the buffer is moved into the returned array, without copying it
+    let shape = (new_height as usize, new_width as usize, layout.channels());
+    Ok(Array3::from_shape_vec(shape, carved)
+        .unwrap()
+        .into_pyarray(py))
+}
+
+// energy of each pixel as a HxW uint32 array, protected pixels having the maximum energy
+#[pyfunction]
+#[pyo3(name = "energy", signature = (img, **options))]
+fn energy_array<'py>(
+    py: Python<'py>,
+    img: PyReadonlyArray3<'py, u8>,
+    options: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Bound<'py, PyArray2<u32>>> {
+    let options = parse_options(options, &ENERGY_OPTIONS)?;
+    let (data, width, height, layout) = pixels_from_array(&img)?;
+    let frame = RawFrame::packed(&data, width, height, layout).map_err(value_error)?;
+    let energy = py
+        .detach(|| {
+            if let Some(protect) = &options.energy.protect {
+                if protect.dimensions() != (width as usize, height as usize) {
+                    return Err(format!(
+                        "mask and image dimensions should be equal, got {:?} and {:?}",
+                        protect.dimensions(),
+                        (width, height)
+                    ));
+                }
+            }
+            let (img, _) = raw::split_channels(&frame);
+            let positions = array::positions_from_image(&img).map_err(|e| e.to_string())?;
+            energy::get_energy_img_parallel(&img, &positions, &options.energy, options.threads)
+                .map_err(|e| e.to_string())
+        })
+        .map_err(PyValueError::new_err)?;
+    Ok(array_into_py(py, energy))
+}
+
+// column of the seam on each row of a HxW uint32 energy array
+#[pyfunction]
+#[pyo3(name = "find_vertical_seam", signature = (energy, **options))]
+fn find_vertical_seam_array<'py>(
+    py: Python<'py>,
+    energy: PyReadonlyArray2<'py, u32>,
+    options: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Bound<'py, PyArray1<usize>>> {
+    let options = parse_options(options, &SEAM_OPTIONS)?;
+    let energy = array_from_py(&energy)?;
+    let seam = py.detach(move || seam::find_vertical_seam_with(&energy, &options.seam));
+    Ok(seam.into_pyarray(py))
+}
+
+// row of the seam on each column of a HxW uint32 energy array
+#[pyfunction]
+#[pyo3(name = "find_horizontal_seam", signature = (energy, **options))]
+fn find_horizontal_seam_array<'py>(
+    py: Python<'py>,
+    energy: PyReadonlyArray2<'py, u32>,
+    options: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Bound<'py, PyArray1<usize>>> {
+    let options = parse_options(options, &SEAM_OPTIONS)?;
+    let energy = array_from_py(&energy)?;
+    let seam = py.detach(move || seam::find_horizontal_seam_with(&energy, &options.seam));
+    Ok(seam.into_pyarray(py))
+}
+
+fn parse_options(kwargs: Option<&Bound<'_, PyDict>>, allowed: &[&str]) -> PyResult<Options> {
+    let mut options = Options::default();
+    let (mut tie_break, mut seed) = (String::from("leftmost"), 0);
+    for (key, value) in kwargs.into_iter().flatten() {
+        let key: String = key.extract()?;
+        if !allowed.contains(&key.as_str()) {
+            return Err(PyTypeError::new_err(format!(
+                "unexpected keyword argument {}",
+                key
+            )));
+        }
+        match key.as_str() {
+            "energy" => {
+                options.energy.energy = value.extract::<String>()?.parse().map_err(value_error)?
+            }
+            "border" => {
+                options.energy.border = value.extract::<String>()?.parse().map_err(value_error)?
+            }
+            "protect" => options.energy.protect = Some(mask_from_py(&value)?),
+            "threads" => options.threads = value.extract::<usize>()?.max(1),
+            "connectivity" => options.seam.connectivity = value.extract::<usize>()?.max(1),
+            "strip" => options.seam.strip = value.extract::<usize>()?.max(1),
+            "tie_break" => tie_break = value.extract::<String>()?,
+            "seed" => seed = value.extract()?,
+            "diagonal_penalty" => options.seam.diagonal_penalty = value.extract()?,
+            "seams_per_pass" => options.seam.seams_per_pass = value.extract::<usize>()?.max(1),
+            "proximity_penalty" => options.seam.proximity_penalty = value.extract()?,
+            "order" => options.order = value.extract::<String>()?.parse().map_err(value_error)?,
+            _ => unreachable!(),
+        }
+    }
+    options.seam.tie_break = parse_tie_break(&tie_break, seed).map_err(value_error)?;
+    Ok(options)
+}
+
+fn parse_tie_break(s: &str, seed: u64) -> Result<TieBreak, Box<dyn Error>> {
+    match s {
+        "leftmost" => Ok(TieBreak::Leftmost),
+        "center" => Ok(TieBreak::Center),
+        "random" => Ok(TieBreak::Random(seed)),
+        _ => Err(format!("tie_break must be leftmost, center or random, got {}", s).into()),
+    }
+}
+
+fn value_error(e: Box<dyn Error>) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+// non-zero (or true) pixels of a HxW array are protected
+fn mask_from_py(value: &Bound<'_, PyAny>) -> PyResult<Array2d<bool>> {
+    match value.extract::<PyReadonlyArray2<'_, bool>>() {
+        Ok(mask) => array_from_py(&mask),
+        Err(_) => {
+            let mask = array_from_py(&value.extract::<PyReadonlyArray2<'_, u8>>()?)?;
+            let (width, data) = (mask.width(), mask.raw_data());
+            Array2d::new(width, data.iter().map(|&p| p > 0).collect()).map_err(value_error)
+        }
+    }
+}
+
+// contiguous arrays are borrowed, the others being copied in a single pass
+fn pixels_from_array<'a>(
+    img: &'a PyReadonlyArray3<'_, u8>,
+) -> PyResult<(Cow<'a, [u8]>, u32, u32, Layout)> {
+    let (height, width, channels) = match img.shape() {
+        &[height, width, channels] => (height, width, channels),
+        _ => unreachable!(),
+    };
+    let (width, height) = match (u32::try_from(width), u32::try_from(height)) {
+        (Ok(width), Ok(height)) => (width, height),
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "images must be at most {} pixels wide and high, got {}x{}",
+                u32::MAX,
+                width,
+                height
+            )))
+        }
+    };
+    let layout = match channels {
+        3 => Layout::Rgb,
+        4 => Layout::Rgba,
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "images must have 3 or 4 channels, got {}",
+                channels
+            )))
+        }
+    };
+    let data = match img.as_slice() {
+        Ok(data) => Cow::Borrowed(data),
+        Err(_) => Cow::Owned(img.as_array().iter().copied().collect()),
+    };
+    Ok((data, width, height, layout))
+}
+
+fn array_into_py<T: Element>(py: Python<'_>, array: Array2d<T>) -> Bound<'_, PyArray2<T>> {
+    let (width, height) = array.dimensions();
+    Array2::from_shape_vec((height, width), array.into_raw_data())
+        .unwrap()
+        .into_pyarray(py)
+}
+
+fn array_from_py<T: Element + Copy>(array: &PyReadonlyArray2<'_, T>) -> PyResult<Array2d<T>> {
+    let width = array.shape()[1];
+    if array.is_empty() {
+        return Err(PyValueError::new_err("arrays must not be empty"));
+    }
+    let data = match array.as_slice() {
+        Ok(data) => data.to_vec(),
+        Err(_) => array.as_array().iter().copied().collect(),
+    };
+    Array2d::new(width, data).map_err(value_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tie_break_parse() {
+        assert_eq!(TieBreak::Random(7), parse_tie_break("random", 7).unwrap());
+        assert_eq!(TieBreak::Center, parse_tie_break("center", 7).unwrap());
+        assert_eq!(
+            Err(String::from(
+                "tie_break must be leftmost, center or random, got first"
+            )),
+            parse_tie_break("first", 0).map_err(|e| format!("{}", e))
+        );
+    }
+}
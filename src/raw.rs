@@ -0,0 +1,319 @@
+use std::error::Error;
+
+use image::{GrayImage, Rgb, RgbImage};
+#[cfg(feature = "ndarray")]
+use ndarray::{Array3, ArrayView3};
+
+use crate::carver::Options;
+use crate::Carver;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Layout {
+    #[default]
+    Rgb,
+    Rgba,
+    Bgra,
+    Gray,
+}
+
+impl Layout {
+    pub fn channels(self) -> usize {
+        match self {
+            Layout::Rgb => 3,
+            Layout::Rgba | Layout::Bgra => 4,
+            Layout::Gray => 1,
+        }
+    }
+}
+
+// pixels owned by the caller, each row starting `stride` bytes after the previous one
+#[derive(Clone, Copy, Debug)]
+pub struct RawFrame<'a> {
+    data: &'a [u8],
+    width: u32,
+    height: u32,
+    stride: usize,
+    layout: Layout,
+}
+
+impl<'a> RawFrame<'a> {
+    pub fn new(
+        data: &'a [u8],
+        width: u32,
+        height: u32,
+        stride: usize,
+        layout: Layout,
+    ) -> Result<Self, Box<dyn Error>> {
+        let row = width as usize * layout.channels();
+        if stride < row {
+            return Err(format!(
+                "stride must be at least {} bytes for {} {:?} pixels, got {}",
+                row, width, layout, stride
+            )
+            .into());
+        }
+        // the last row needs no padding
+        let size = match height {
+            0 => 0,
+            height => stride * (height as usize - 1) + row,
+        };
+        if data.len() < size {
+            return Err(format!(
+                "data should hold at least {} bytes for {}x{} pixels, got {}",
+                size,
+                width,
+                height,
+                data.len()
+            )
+            .into());
+        }
+        Ok(Self {
+            data,
+            width,
+            height,
+            stride,
+            layout,
+        })
+    }
+
+    // rows packed one after the other, without padding
+    pub fn packed(
+        data: &'a [u8],
+        width: u32,
+        height: u32,
+        layout: Layout,
+    ) -> Result<Self, Box<dyn Error>> {
+        Self::new(
+            data,
+            width,
+            height,
+            width as usize * layout.channels(),
+            layout,
+        )
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    fn pixels(&self) -> impl Iterator<Item = &'a [u8]> {
+        let row = self.width as usize * self.layout.channels();
+        let channels = self.layout.channels();
+        self.data
+            .chunks(self.stride.max(1))
+            .take(self.height as usize)
+            .flat_map(move |pixels| pixels[..row].chunks_exact(channels))
+    }
+}
+
+// carves the frame, returning its pixels in the same layout with packed rows; the seams are
+// found on the colors, any alpha channel following them
+pub fn seamcarve_raw(
+    frame: &RawFrame<'_>,
+    new_width: u32,
+    new_height: u32,
+    options: &Options,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let (img, alpha) = split_channels(frame);
+    let mut carver = Carver::new(&img).with_options(options.clone());
+    let (img_carved, alpha_carved) = match &alpha {
+        Some(alpha) => {
+            let (img_carved, alpha_carved) =
+                carver.resize_with_alpha(alpha, new_width, new_height)?;
+            (img_carved, Some(alpha_carved))
+        }
+        None => (carver.resize(new_width, new_height)?, None),
+    };
+    Ok(merge_channels(
+        &img_carved,
+        alpha_carved.as_ref(),
+        frame.layout,
+    ))
+}
+
+// takes a HxWxC image, C being 1 (gray), 3 (rgb) or 4 (rgba), and returns the carved one with
+// the same channels
+#[cfg(feature = "ndarray")]
+pub fn seamcarve_ndarray(
+    img: ArrayView3<'_, u8>,
+    new_width: u32,
+    new_height: u32,
+) -> Result<Array3<u8>, Box<dyn Error>> {
+    let (height, width, channels) = img.dim();
+    let layout = match channels {
+        1 => Layout::Gray,
+        3 => Layout::Rgb,
+        4 => Layout::Rgba,
+        _ => return Err(format!("images must have 1, 3 or 4 channels, got {}", channels).into()),
+    };
+    // only views that are not in standard layout are copied
+    let img = img.as_standard_layout();
+    let frame = RawFrame::packed(img.as_slice().unwrap(), width as u32, height as u32, layout)?;
+    let carved = seamcarve_raw(&frame, new_width, new_height, &Options::default())?;
+    let shape = (new_height as usize, new_width as usize, channels);
+    Ok(Array3::from_shape_vec(shape, carved).unwrap())
+}
+
+// gray pixels are carved as rgb ones with equal channels, which only scales their energy
+pub(crate) fn split_channels(frame: &RawFrame<'_>) -> (RgbImage, Option<GrayImage>) {
+    let (width, height) = (frame.width, frame.height);
+    let rgb = frame
+        .pixels()
+        .flat_map(|pixel| match frame.layout {
+            Layout::Rgb | Layout::Rgba => [pixel[0], pixel[1], pixel[2]],
+            Layout::Bgra => [pixel[2], pixel[1], pixel[0]],
+            Layout::Gray => [pixel[0]; 3],
+        })
+        .collect();
+    let alpha = match frame.layout {
+        Layout::Rgba | Layout::Bgra => {
+            let alpha = frame.pixels().map(|pixel| pixel[3]).collect();
+            Some(GrayImage::from_raw(width, height, alpha).unwrap())
+        }
+        Layout::Rgb | Layout::Gray => None,
+    };
+    (RgbImage::from_raw(width, height, rgb).unwrap(), alpha)
+}
+
+fn merge_channels(img: &RgbImage, alpha: Option<&GrayImage>, layout: Layout) -> Vec<u8> {
+    let mut data = Vec::with_capacity(img.len() / 3 * layout.channels());
+    for (x, y, &Rgb([r, g, b])) in img.enumerate_pixels() {
+        let a = || alpha.map_or(u8::MAX, |alpha| alpha.get_pixel(x, y).0[0]);
+        match layout {
+            Layout::Rgb => data.extend_from_slice(&[r, g, b]),
+            Layout::Rgba => data.extend_from_slice(&[r, g, b, a()]),
+            Layout::Bgra => data.extend_from_slice(&[b, g, r, a()]),
+            Layout::Gray => data.push(r),
+        }
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::gradient_img;
+
+    #[test]
+    fn raw_layouts() {
+        let img = gradient_img(12, 10);
+        let options = Options::default();
+        let expected = Carver::new(&img).resize(8, 11).unwrap();
+
+        let rgb = RawFrame::packed(img.as_raw(), 12, 10, Layout::Rgb).unwrap();
+        assert_eq!(
+            expected.as_raw(),
+            &seamcarve_raw(&rgb, 8, 11, &options).unwrap()
+        );
+
+        let alpha = GrayImage::from_fn(12, 10, |x, _| image::Luma([(x * 20) as u8]));
+        let (_, expected_alpha) = Carver::new(&img).resize_with_alpha(&alpha, 8, 11).unwrap();
+        let bgra: Vec<u8> = img
+            .pixels()
+            .zip(alpha.pixels())
+            .flat_map(|(&Rgb([r, g, b]), a)| [b, g, r, a.0[0]])
+            .collect();
+        let bgra = RawFrame::packed(&bgra, 12, 10, Layout::Bgra).unwrap();
+        let carved = seamcarve_raw(&bgra, 8, 11, &options).unwrap();
+        let expected_bgra: Vec<u8> = expected
+            .pixels()
+            .zip(expected_alpha.pixels())
+            .flat_map(|(&Rgb([r, g, b]), a)| [b, g, r, a.0[0]])
+            .collect();
+        assert_eq!(expected_bgra, carved);
+
+        let gray: Vec<u8> = img.pixels().map(|pixel| pixel.0[0]).collect();
+        let gray_img = RgbImage::from_fn(12, 10, |x, y| Rgb([gray[(y * 12 + x) as usize]; 3]));
+        let expected = Carver::new(&gray_img).resize(8, 11).unwrap();
+        let gray = RawFrame::packed(&gray, 12, 10, Layout::Gray).unwrap();
+        let carved = seamcarve_raw(&gray, 8, 11, &options).unwrap();
+        assert_eq!(
+            expected
+                .pixels()
+                .map(|pixel| pixel.0[0])
+                .collect::<Vec<u8>>(),
+            carved
+        );
+    }
+
+    #[test]
+    fn raw_stride() {
+        let img = gradient_img(12, 10);
+        // rows padded to 64 bytes, the last one being cut short
+        let mut rgba = vec![0; 64 * 9 + 48];
+        for (x, y, &Rgb([r, g, b])) in img.enumerate_pixels() {
+            let offset = (y * 64 + x * 4) as usize;
+            rgba[offset..offset + 4].copy_from_slice(&[r, g, b, 255]);
+        }
+        let frame = RawFrame::new(&rgba, 12, 10, 64, Layout::Rgba).unwrap();
+        let carved = seamcarve_raw(&frame, 8, 10, &Options::default()).unwrap();
+        let expected: Vec<u8> = Carver::new(&img)
+            .resize(8, 10)
+            .unwrap()
+            .pixels()
+            .flat_map(|&Rgb([r, g, b])| [r, g, b, 255])
+            .collect();
+        assert_eq!(expected, carved);
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn ndarray_image() {
+        let img = gradient_img(12, 10);
+        let expected = Carver::new(&img).resize(8, 11).unwrap();
+        let array = Array3::from_shape_vec((10, 12, 3), img.to_vec()).unwrap();
+        let carved = seamcarve_ndarray(array.view(), 8, 11).unwrap();
+        assert_eq!((11, 8, 3), carved.dim());
+        assert_eq!(expected.as_raw(), carved.as_slice().unwrap());
+
+        // a view in fortran order is carved the same
+        let mut fortran = Array3::zeros(ndarray::ShapeBuilder::f((10, 12, 3)));
+        fortran.assign(&array);
+        assert!(!fortran.is_standard_layout());
+        assert_eq!(carved, seamcarve_ndarray(fortran.view(), 8, 11).unwrap());
+
+        assert_eq!(
+            Err(String::from("images must have 1, 3 or 4 channels, got 2")),
+            seamcarve_ndarray(Array3::zeros((10, 12, 2)).view(), 8, 11)
+                .map_err(|e| format!("{}", e))
+        );
+    }
+
+    #[test]
+    fn raw_errors() {
+        let data = vec![0; 100];
+        assert_eq!(
+            Err(String::from(
+                "stride must be at least 48 bytes for 12 Rgba pixels, got 40"
+            )),
+            RawFrame::new(&data, 12, 10, 40, Layout::Rgba)
+                .map(|_| ())
+                .map_err(|e| format!("{}", e))
+        );
+        assert_eq!(
+            Err(String::from(
+                "data should hold at least 120 bytes for 12x10 pixels, got 100"
+            )),
+            RawFrame::packed(&data, 12, 10, Layout::Gray)
+                .map(|_| ())
+                .map_err(|e| format!("{}", e))
+        );
+        let frame = RawFrame::packed(&data, 10, 10, Layout::Gray).unwrap();
+        assert_eq!(
+            Err(String::from("new_width must be positive, got 0")),
+            seamcarve_raw(&frame, 0, 10, &Options::default()).map_err(|e| format!("{}", e))
+        );
+    }
+}
@@ -0,0 +1,428 @@
+use std::error::Error;
+#[cfg(feature = "fs")]
+use std::fs::File;
+#[cfg(feature = "fs")]
+use std::io::{BufReader, BufWriter};
+use std::io::{Read, Write};
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+use image::RgbImage;
+
+use crate::array::{self, Array2d};
+use crate::energy::{self, EnergyOptions};
+use crate::seam;
+
+const MAGIC: &[u8; 4] = b"RSCM";
+const VERSION: u32 = 1;
+const NOT_REMOVED: u32 = u32::MAX;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RetargetMap {
+    vertical: Array2d<u32>,
+    horizontal: Array2d<u32>,
+    vertical_removed: u32,
+    horizontal_removed: u32,
+}
+
+impl RetargetMap {
+    pub fn new(img: &RgbImage) -> Result<Self, Box<dyn Error>> {
+        Self::with_options(img, &EnergyOptions::default(), 1, 1)
+    }
+
+    pub fn with_options(
+        img: &RgbImage,
+        options: &EnergyOptions,
+        min_width: u32,
+        min_height: u32,
+    ) -> Result<Self, Box<dyn Error>> {
+        let (width, height) = img.dimensions();
+        if min_width == 0 || min_width > width {
+            return Err(format!(
+                "min_width must be between 1 and the image width, got {} and {}",
+                min_width, width
+            )
+            .into());
+        }
+        if min_height == 0 || min_height > height {
+            return Err(format!(
+                "min_height must be between 1 and the image height, got {} and {}",
+                min_height, height
+            )
+            .into());
+        }
+        let (vertical_removed, horizontal_removed) = (width - min_width, height - min_height);
+        Ok(Self {
+            vertical: index_map(img, options, vertical_removed, false)?,
+            horizontal: index_map(img, options, horizontal_removed, true)?,
+            vertical_removed,
+            horizontal_removed,
+        })
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        let (width, height) = self.vertical.dimensions();
+        (width as u32, height as u32)
+    }
+
+    pub fn min_dimensions(&self) -> (u32, u32) {
+        let (width, height) = self.dimensions();
+        (
+            width - self.vertical_removed,
+            height - self.horizontal_removed,
+        )
+    }
+
+    pub fn vertical(&self) -> &Array2d<u32> {
+        &self.vertical
+    }
+
+    pub fn horizontal(&self) -> &Array2d<u32> {
+        &self.horizontal
+    }
+
+    pub fn resize_width(&self, img: &RgbImage, new_width: u32) -> Result<RgbImage, Box<dyn Error>> {
+        self.check_image(img)?;
+        let (width, height) = img.dimensions();
+        let min_width = self.min_dimensions().0;
+        if new_width < min_width || new_width > width {
+            return Err(format!(
+                "new_width must be between {} and {}, got {}",
+                min_width, width, new_width
+            )
+            .into());
+        }
+        let cut = width - new_width;
+        let mut data = Vec::with_capacity((new_width * height * 3) as usize);
+        for y in 0..height {
+            let row_start = data.len();
+            for x in 0..width {
+                if self.vertical[(x as usize, y as usize)] >= cut {
+                    data.extend_from_slice(&img.get_pixel(x, y).0);
+                }
+            }
+            if data.len() - row_start != new_width as usize * 3 {
+                return Err(
+                    format!("corrupt retarget map, row {} is not a valid seam order", y).into(),
+                );
+            }
+        }
+        RgbImage::from_raw(new_width, height, data).ok_or_else(|| "corrupt retarget map".into())
+    }
+
+    pub fn resize_height(
+        &self,
+        img: &RgbImage,
+        new_height: u32,
+    ) -> Result<RgbImage, Box<dyn Error>> {
+        self.check_image(img)?;
+        let (width, height) = img.dimensions();
+        let min_height = self.min_dimensions().1;
+        if new_height < min_height || new_height > height {
+            return Err(format!(
+                "new_height must be between {} and {}, got {}",
+                min_height, height, new_height
+            )
+            .into());
+        }
+        let cut = height - new_height;
+        let mut new_img = RgbImage::new(width, new_height);
+        for x in 0..width {
+            let mut new_y = 0;
+            for y in 0..height {
+                if self.horizontal[(x as usize, y as usize)] >= cut {
+                    if new_y == new_height {
+                        return Err(format!(
+                            "corrupt retarget map, column {} is not a valid seam order",
+                            x
+                        )
+                        .into());
+                    }
+                    new_img.put_pixel(x, new_y, *img.get_pixel(x, y));
+                    new_y += 1;
+                }
+            }
+            if new_y != new_height {
+                return Err(format!(
+                    "corrupt retarget map, column {} is not a valid seam order",
+                    x
+                )
+                .into());
+            }
+        }
+        Ok(new_img)
+    }
+
+    #[cfg(feature = "fs")]
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    #[cfg(feature = "fs")]
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        Self::read_from(&mut BufReader::new(File::open(path)?))
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), Box<dyn Error>> {
+        let (width, height) = self.dimensions();
+        writer.write_all(MAGIC)?;
+        for value in [
+            VERSION,
+            width,
+            height,
+            self.vertical_removed,
+            self.horizontal_removed,
+        ] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        for &index in self
+            .vertical
+            .raw_data()
+            .iter()
+            .chain(self.horizontal.raw_data())
+        {
+            writer.write_all(&index.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self, Box<dyn Error>> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err("not a retarget map file".into());
+        }
+        let version = read_u32(reader)?;
+        if version != VERSION {
+            return Err(format!("unsupported retarget map version, got {}", version).into());
+        }
+        let (width, height) = (read_u32(reader)?, read_u32(reader)?);
+        let (vertical_removed, horizontal_removed) = (read_u32(reader)?, read_u32(reader)?);
+        if width == 0 || height == 0 || vertical_removed >= width || horizontal_removed >= height {
+            return Err(format!(
+                "invalid retarget map header, got {}x{} with {} and {} seams",
+                width, height, vertical_removed, horizontal_removed
+            )
+            .into());
+        }
+        let size = (width as usize)
+            .checked_mul(height as usize)
+            .ok_or_else(|| format!("retarget map too large, got {}x{}", width, height))?;
+        let read_map = |reader: &mut R| -> Result<Array2d<u32>, Box<dyn Error>> {
+            // grown while reading, so that a truncated file cannot claim a huge allocation
+            let mut data = Vec::new();
+            for _ in 0..size {
+                data.push(read_u32(reader)?);
+            }
+            Array2d::new(width as usize, data)
+        };
+        let vertical = read_map(reader)?;
+        check_index_map(&vertical, vertical_removed, "vertical")?;
+        let mut horizontal = read_map(reader)?;
+        horizontal.transpose();
+        check_index_map(&horizontal, horizontal_removed, "horizontal")?;
+        horizontal.transpose();
+        Ok(Self {
+            vertical,
+            horizontal,
+            vertical_removed,
+            horizontal_removed,
+        })
+    }
+
+    fn check_image(&self, img: &RgbImage) -> Result<(), Box<dyn Error>> {
+        if img.dimensions() != self.dimensions() {
+            return Err(format!(
+                "image and retarget map dimensions should be equal, got {:?} and {:?}",
+                img.dimensions(),
+                self.dimensions()
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+fn index_map(
+    img: &RgbImage,
+    options: &EnergyOptions,
+    to_remove: u32,
+    horizontal: bool,
+) -> Result<Array2d<u32>, Box<dyn Error>> {
+    let (width, height) = img.dimensions();
+    let size = (width as usize)
+        .checked_mul(height as usize)
+        .ok_or_else(|| format!("retarget map too large, got {}x{}", width, height))?;
+    let mut map = Array2d::new(width as usize, vec![NOT_REMOVED; size])?;
+    let mut positions = array::positions_from_image(img)?;
+    if horizontal {
+        positions.transpose();
+    }
+    let mut energy_map = energy::get_energy_img_with(img, &positions, options)?;
+    let mut seam;
+    for i in 0..to_remove {
+        seam = seam::find_vertical_seam(&energy_map);
+        seam.iter().enumerate().for_each(|(y, &x)| {
+            let (px, py) = positions[(x, y)];
+            map[(px as usize, py as usize)] = i;
+        });
+        positions.remove_seam(&seam)?;
+        energy::update_energy_img_with(&mut energy_map, img, &positions, &seam, options)?;
+    }
+    Ok(map)
+}
+
+// each row must hold the index of every seam removed from it exactly once, the remaining pixels
+// being never removed
+fn check_index_map(map: &Array2d<u32>, removed: u32, name: &str) -> Result<(), Box<dyn Error>> {
+    let mut seen = vec![false; removed as usize];
+    for (i, row) in map.rows().enumerate() {
+        seen.iter_mut().for_each(|seen| *seen = false);
+        for &index in row {
+            if index == NOT_REMOVED {
+                continue;
+            }
+            match seen.get_mut(index as usize) {
+                Some(seen) if !*seen => *seen = true,
+                _ => {
+                    return Err(format!(
+                        "corrupt {} retarget map, got index {} at line {}",
+                        name, index, i
+                    )
+                    .into())
+                }
+            }
+        }
+        if seen.contains(&false) {
+            return Err(
+                format!("corrupt {} retarget map, missing seams at line {}", name, i).into(),
+            );
+        }
+    }
+    Ok(())
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, Box<dyn Error>> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::gradient_img;
+    use crate::Carver;
+
+    #[test]
+    fn resize_width() {
+        let img = gradient_img(12, 10);
+        let map = RetargetMap::new(&img).unwrap();
+        assert_eq!((1, 1), map.min_dimensions());
+        for new_width in 1..=12 {
+            assert_eq!(
+                Carver::new(&img).resize(new_width, 10).unwrap(),
+                map.resize_width(&img, new_width).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn resize_height() {
+        let img = gradient_img(12, 10);
+        let map = RetargetMap::new(&img).unwrap();
+        for new_height in 1..=10 {
+            assert_eq!(
+                Carver::new(&img).resize(12, new_height).unwrap(),
+                map.resize_height(&img, new_height).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn resize_below_minimum() {
+        let img = gradient_img(12, 10);
+        let map = RetargetMap::with_options(&img, &EnergyOptions::default(), 6, 5).unwrap();
+        assert_eq!((6, 5), map.min_dimensions());
+        assert_eq!(
+            Err(String::from("new_width must be between 6 and 12, got 5")),
+            map.resize_width(&img, 5).map_err(|e| format!("{}", e))
+        );
+    }
+
+    #[test]
+    fn write_read() {
+        let img = gradient_img(12, 10);
+        let map = RetargetMap::with_options(&img, &EnergyOptions::default(), 3, 4).unwrap();
+        let mut bytes = vec![];
+        map.write_to(&mut bytes).unwrap();
+        assert_eq!(4 + 5 * 4 + 2 * 12 * 10 * 4, bytes.len());
+        assert_eq!(map, RetargetMap::read_from(&mut bytes.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn read_invalid() {
+        assert_eq!(
+            Err(String::from("not a retarget map file")),
+            RetargetMap::read_from(&mut b"RSCX".as_ref()).map_err(|e| format!("{}", e))
+        );
+    }
+
+    #[test]
+    fn read_truncated() {
+        let img = gradient_img(12, 10);
+        let map = RetargetMap::with_options(&img, &EnergyOptions::default(), 3, 4).unwrap();
+        let mut bytes = vec![];
+        map.write_to(&mut bytes).unwrap();
+        bytes.truncate(bytes.len() - 2);
+        assert_eq!(
+            Err(String::from("failed to fill whole buffer")),
+            RetargetMap::read_from(&mut bytes.as_slice()).map_err(|e| format!("{}", e))
+        );
+
+        // a header claiming a huge map fails on the missing data, without reserving it first
+        let mut bytes = MAGIC.to_vec();
+        for value in [VERSION, u32::MAX, u32::MAX, 1, 1] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        assert_eq!(
+            Err(String::from("failed to fill whole buffer")),
+            RetargetMap::read_from(&mut bytes.as_slice()).map_err(|e| format!("{}", e))
+        );
+    }
+
+    #[test]
+    fn read_corrupted() {
+        let img = gradient_img(12, 10);
+        let map = RetargetMap::with_options(&img, &EnergyOptions::default(), 3, 4).unwrap();
+        let mut bytes = vec![];
+        map.write_to(&mut bytes).unwrap();
+        let header = 4 + 5 * 4;
+
+        // the first row of the vertical map never removed
+        let mut corrupted = bytes.clone();
+        for offset in (header..header + 12 * 4).step_by(4) {
+            corrupted[offset..offset + 4].copy_from_slice(&NOT_REMOVED.to_le_bytes());
+        }
+        assert_eq!(
+            Err(String::from(
+                "corrupt vertical retarget map, missing seams at line 0"
+            )),
+            RetargetMap::read_from(&mut corrupted.as_slice()).map_err(|e| format!("{}", e))
+        );
+
+        // an index beyond the removed seams in the horizontal map
+        let mut corrupted = bytes;
+        let offset = header + 12 * 10 * 4;
+        corrupted[offset..offset + 4].copy_from_slice(&6u32.to_le_bytes());
+        assert_eq!(
+            Err(String::from(
+                "corrupt horizontal retarget map, got index 6 at line 0"
+            )),
+            RetargetMap::read_from(&mut corrupted.as_slice()).map_err(|e| format!("{}", e))
+        );
+    }
+}
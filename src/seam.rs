@@ -1,18 +1,211 @@
-use image::{Pixel, Rgb, RgbImage};
-use num_traits::ToPrimitive;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::{mem, slice};
+
+#[cfg(feature = "image")]
+use image::{ImageBuffer, Pixel};
 
 use crate::array::Array2d;
+use crate::maxflow::{Graph, INFINITE};
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TieBreak {
+    // straight down, then left, then right, starting from the leftmost column
+    #[default]
+    Leftmost,
+    Center,
+    Random(u64),
+}
+
+impl TieBreak {
+    fn start_rank(self, x: usize, width: usize) -> u64 {
+        match self {
+            TieBreak::Leftmost => x as u64,
+            TieBreak::Center => ((2 * x).abs_diff(width - 1) as u64) << 32 | x as u64,
+            TieBreak::Random(seed) => mix(seed ^ mix(x as u64)),
+        }
+    }
+
+    fn move_rank(self, x: usize, next: usize, y: usize, width: usize) -> u64 {
+        let offset = x.abs_diff(next) as u64;
+        match self {
+            TieBreak::Leftmost => 2 * offset + (next > x) as u64,
+            TieBreak::Center => {
+                let outwards = (2 * next).abs_diff(width - 1) > (2 * x).abs_diff(width - 1);
+                2 * offset + outwards as u64
+            }
+            TieBreak::Random(seed) => {
+                // wraps for huge images, where the ranks only need to stay well mixed
+                let (x, y, next, width) = (x as u64, y as u64, next as u64, width as u64);
+                let index = y
+                    .wrapping_mul(width)
+                    .wrapping_add(x)
+                    .wrapping_mul(width)
+                    .wrapping_add(next);
+                mix(seed ^ mix(index))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SeamOptions {
+    pub seams_per_pass: usize,
+    pub connectivity: usize,
+    pub strip: usize,
+    pub tie_break: TieBreak,
+    pub diagonal_penalty: u32,
+    pub proximity_penalty: u32,
+}
+
+impl Default for SeamOptions {
+    fn default() -> Self {
+        Self {
+            seams_per_pass: 1,
+            connectivity: 1,
+            strip: 1,
+            tie_break: TieBreak::default(),
+            diagonal_penalty: 0,
+            proximity_penalty: 0,
+        }
+    }
+}
 
 pub fn find_vertical_seam(energy: &Array2d<u32>) -> Vec<usize> {
-    let (width, height, size) = (energy.width(), energy.height(), energy.size());
-    let mut cost = Array2d::new(width, vec![0; size]).unwrap();
+    find_vertical_seam_with(energy, &SeamOptions::default())
+}
+
+pub fn find_vertical_seam_with(energy: &Array2d<u32>, options: &SeamOptions) -> Vec<usize> {
+    find_seam(energy, false, options)
+}
+
+// a horizontal seam is searched along the columns, as if the energy was transposed
+fn find_seam(energy: &Array2d<u32>, horizontal: bool, options: &SeamOptions) -> Vec<usize> {
+    let (cost, path) = cumulative_cost(energy, horizontal, options);
+    let (width, bands) = cost.dimensions();
+    let mut seam = Vec::with_capacity(bands);
+    seam.push(
+        (0..width)
+            .min_by_key(|&x| (cost[(x, 0)], options.tie_break.start_rank(x, width)))
+            .unwrap(),
+    );
+    for band in 0..(bands - 1) {
+        seam.push(path[(seam[band], band)])
+    }
+    expand_bands(&seam, seam_length(energy, horizontal), options.strip)
+}
+
+pub fn find_vertical_seams(energy: &Array2d<u32>, to_find: usize) -> Vec<Vec<usize>> {
+    find_vertical_seams_with(energy, to_find, &SeamOptions::default())
+}
+
+pub fn find_vertical_seams_with(
+    energy: &Array2d<u32>,
+    to_find: usize,
+    options: &SeamOptions,
+) -> Vec<Vec<usize>> {
+    find_seams(energy, false, to_find, options)
+}
+
+pub fn find_horizontal_seams(energy: &Array2d<u32>, to_find: usize) -> Vec<Vec<usize>> {
+    find_horizontal_seams_with(energy, to_find, &SeamOptions::default())
+}
+
+pub fn find_horizontal_seams_with(
+    energy: &Array2d<u32>,
+    to_find: usize,
+    options: &SeamOptions,
+) -> Vec<Vec<usize>> {
+    find_seams(energy, true, to_find, options)
+}
+
+fn find_seams(
+    energy: &Array2d<u32>,
+    horizontal: bool,
+    to_find: usize,
+    options: &SeamOptions,
+) -> Vec<Vec<usize>> {
+    let (cost, path) = cumulative_cost(energy, horizontal, options);
+    let width = cost.width();
+    let mut starts: Vec<usize> = (0..width).collect();
+    starts.sort_by_key(|&x| (cost[(x, 0)], options.tie_break.start_rank(x, width)));
+    // seams found so far, from left to right, the order being the same on every band
+    let mut found: Vec<Vec<usize>> = Vec::with_capacity(to_find);
+    let mut seams = Vec::with_capacity(to_find);
+    for start in starts {
+        if seams.len() == to_find {
+            break;
+        }
+        let index = found.partition_point(|seam| seam[0] < start);
+        if found.get(index).is_some_and(|seam| seam[0] == start) {
+            continue;
+        }
+        let (left, right) = (index.checked_sub(1).map(|i| &found[i]), found.get(index));
+        if let Some(seam) = backtrack_between(&cost, &path, left, right, start, options) {
+            seams.push(expand_bands(
+                &seam,
+                seam_length(energy, horizontal),
+                options.strip,
+            ));
+            found.insert(index, seam);
+        }
+    }
+    seams
+}
+
+fn seam_length(energy: &Array2d<u32>, horizontal: bool) -> usize {
+    match horizontal {
+        true => energy.width(),
+        false => energy.height(),
+    }
+}
+
+// cost and path are laid out along the seams, i.e. transposed for horizontal ones
+fn cumulative_cost(
+    energy: &Array2d<u32>,
+    horizontal: bool,
+    options: &SeamOptions,
+) -> (Array2d<u64>, Array2d<usize>) {
+    if options.strip > 1 {
+        let banded = band_energy(energy, horizontal, options.strip);
+        return cumulative_cost_by(banded.dimensions(), |x, y| banded[(x, y)], options);
+    }
+    match horizontal {
+        true => cumulative_cost_by(
+            (energy.height(), energy.width()),
+            |x, y| energy[(y, x)],
+            options,
+        ),
+        false => cumulative_cost_by(energy.dimensions(), |x, y| energy[(x, y)], options),
+    }
+}
+
+fn cumulative_cost_by<E: Fn(usize, usize) -> u32>(
+    (width, height): (usize, usize),
+    energy: E,
+    options: &SeamOptions,
+) -> (Array2d<u64>, Array2d<usize>) {
+    let size = width * height;
+    let mut cost = Array2d::new(width, vec![0u64; size]).unwrap(); // u64 so protected pixels cannot overflow
     let mut path = Array2d::new(width, vec![0; size - width]).unwrap();
-    let mut seam = Vec::with_capacity(height);
     for x in 0..width {
-        cost[(x, height - 1)] = energy[(x, height - 1)];
+        cost[(x, height - 1)] = energy(x, height - 1) as u64;
     }
     for y in (0..(height - 1)).rev() {
         for x in 0..width {
+            if options.tie_break != TieBreak::Leftmost || options.diagonal_penalty > 0 {
+                let (best_index, (min_cost, _)) = (x.saturating_sub(options.connectivity)
+                    ..=(x + options.connectivity).min(width - 1))
+                    .map(|i| (i, move_key(&cost, x, i, y, options)))
+                    .min_by_key(|&(_, key)| key)
+                    .unwrap();
+                path[(x, y)] = best_index;
+                cost[(x, y)] = energy(x, y) as u64 + min_cost;
+                continue;
+            }
             let (mut best_index, mut min_cost) = (x, cost[(x, y + 1)]);
             if x > 0 && cost[(x - 1, y + 1)] < min_cost {
                 best_index = x - 1;
@@ -22,21 +215,226 @@ pub fn find_vertical_seam(energy: &Array2d<u32>) -> Vec<usize> {
                 best_index = x + 1;
                 min_cost = cost[(x + 1, y + 1)]
             }
+            for offset in 2..=options.connectivity {
+                if x >= offset && cost[(x - offset, y + 1)] < min_cost {
+                    best_index = x - offset;
+                    min_cost = cost[(x - offset, y + 1)]
+                }
+                if x + offset < width && cost[(x + offset, y + 1)] < min_cost {
+                    best_index = x + offset;
+                    min_cost = cost[(x + offset, y + 1)]
+                }
+            }
             path[(x, y)] = best_index;
-            cost[(x, y)] = energy[(x, y)] + min_cost;
+            cost[(x, y)] = energy(x, y) as u64 + min_cost;
+        }
+    }
+    (cost, path)
+}
+
+// cost of moving from (x, y) to (next, y + 1), ranked by the tie-breaking policy on ties
+fn move_key(
+    cost: &Array2d<u64>,
+    x: usize,
+    next: usize,
+    y: usize,
+    options: &SeamOptions,
+) -> (u64, u64) {
+    let penalty = x.abs_diff(next) as u64 * options.diagonal_penalty as u64;
+    let rank = options.tie_break.move_rank(x, next, y, cost.width());
+    (cost[(next, y + 1)] + penalty, rank)
+}
+
+// sums the rows (or columns) of each band of `strip` of them, so that a seam is straight within a
+// band, the bands being laid out along the seams
+fn band_energy(energy: &Array2d<u32>, horizontal: bool, strip: usize) -> Array2d<u32> {
+    let (width, height) = energy.dimensions();
+    let (across, along) = match horizontal {
+        true => (height, width),
+        false => (width, height),
+    };
+    let mut data = vec![0u32; across * along.div_ceil(strip)];
+    for ((x, y), &value) in energy.iter_indexed() {
+        let (i, j) = match horizontal {
+            true => (y, x),
+            false => (x, y),
+        };
+        let sum = &mut data[j / strip * across + i];
+        *sum = sum.saturating_add(value);
+    }
+    Array2d::new(across, data).unwrap()
+}
+
+fn expand_bands(seam: &[usize], height: usize, strip: usize) -> Vec<usize> {
+    if strip == 1 {
+        return seam.to_vec();
+    }
+    (0..height).map(|y| seam[y / strip]).collect()
+}
+
+// follows the optimal path, detouring to the cheapest neighbour within the gap between the
+// seams on either side when it leaves it, so that seams never share a pixel nor cross
+fn backtrack_between(
+    cost: &Array2d<u64>,
+    path: &Array2d<usize>,
+    left: Option<&Vec<usize>>,
+    right: Option<&Vec<usize>>,
+    start: usize,
+    options: &SeamOptions,
+) -> Option<Vec<usize>> {
+    let (width, bands) = cost.dimensions();
+    let mut seam = Vec::with_capacity(bands);
+    seam.push(start);
+    for band in 0..(bands - 1) {
+        let x = seam[band];
+        let low = left.map_or(0, |left| left[band + 1] + 1);
+        let high = match right {
+            Some(right) => right[band + 1].checked_sub(1)?,
+            None => width - 1,
+        };
+        let mut next = path[(x, band)];
+        if next < low || next > high {
+            let connectivity = options.connectivity;
+            next = (x.saturating_sub(connectivity).max(low)..=(x + connectivity).min(high))
+                .min_by_key(|&i| move_key(cost, x, i, band, options))?;
+        }
+        seam.push(next);
+    }
+    Some(seam)
+}
+
+// min-cut formulation of the search, the minimal cut being a seam of optimal cost; it is much
+// slower than the DP and only pays off for frame stacks
+pub fn find_vertical_seam_graph_cut(energy: &Array2d<u32>) -> Vec<usize> {
+    find_vertical_seam_surface(slice::from_ref(energy))
+        .unwrap()
+        .pop()
+        .unwrap()
+}
+
+// one seam per frame, seams of consecutive frames being connected like consecutive rows
+pub fn find_vertical_seam_surface(
+    frames: &[Array2d<u32>],
+) -> Result<Vec<Vec<usize>>, Box<dyn Error>> {
+    let (width, height) = match frames.first() {
+        Some(frame) => frame.dimensions(),
+        None => return Err("at least one frame is needed to find a seam".into()),
+    };
+    for frame in frames {
+        if frame.dimensions() != (width, height) {
+            return Err(format!(
+                "frame dimensions should be equal, got {:?} and {:?}",
+                frame.dimensions(),
+                (width, height)
+            )
+            .into());
+        }
+    }
+    // each row has one node per boundary between pixels, cutting the edge from node x to
+    // node x + 1 removes pixel x
+    let row_nodes = width + 1;
+    let node = |t: usize, y: usize, x: usize| (t * height + y) * row_nodes + x;
+    let mut graph = Graph::with_capacity(
+        frames.len() * height * row_nodes,
+        5 * frames.len() * height * width,
+    );
+    for (t, frame) in frames.iter().enumerate() {
+        for y in 0..height {
+            graph.add_terminal(node(t, y, 0), INFINITE, 0);
+            graph.add_terminal(node(t, y, width), 0, INFINITE);
+            for x in 0..width {
+                // the infinite reverse capacity makes the cut cross each row exactly once
+                graph.add_edge(
+                    node(t, y, x),
+                    node(t, y, x + 1),
+                    frame[(x, y)] as u64,
+                    INFINITE,
+                );
+            }
+        }
+    }
+    // infinite diagonal edges keep the cuts of neighbouring rows and frames at most one
+    // pixel apart
+    let mut connect = |first: usize, second: usize| {
+        for x in 1..row_nodes {
+            graph.add_edge(first + x, second + x - 1, INFINITE, 0);
+            graph.add_edge(second + x, first + x - 1, INFINITE, 0);
+        }
+    };
+    for t in 0..frames.len() {
+        for y in 0..height {
+            if y + 1 < height {
+                connect(node(t, y, 0), node(t, y + 1, 0));
+            }
+            if t + 1 < frames.len() {
+                connect(node(t, y, 0), node(t + 1, y, 0));
+            }
+        }
+    }
+    graph.maxflow();
+    let mut seams = Vec::with_capacity(frames.len());
+    for t in 0..frames.len() {
+        let seam = (0..height)
+            .map(|y| {
+                (1..width)
+                    .take_while(|&x| graph.is_source(node(t, y, x)))
+                    .count()
+            })
+            .collect();
+        seams.push(seam);
+    }
+    Ok(seams)
+}
+
+// the dedicated column-wise search is kept for the default options
+pub fn find_horizontal_seam_with(energy: &Array2d<u32>, options: &SeamOptions) -> Vec<usize> {
+    if options.connectivity == 1
+        && options.strip == 1
+        && options.tie_break == TieBreak::Leftmost
+        && options.diagonal_penalty == 0
+    {
+        return find_horizontal_seam(energy);
+    }
+    find_seam(energy, true, options)
+}
+
+pub fn find_horizontal_seam(energy: &Array2d<u32>) -> Vec<usize> {
+    let (width, height) = (energy.width(), energy.height());
+    let mut cost_next: Vec<u64> = (0..height).map(|y| energy[(width - 1, y)] as u64).collect();
+    let mut cost = vec![0u64; height];
+    let mut path = vec![0; (width - 1) * height]; // column-major, one column per x
+    let mut seam = Vec::with_capacity(width);
+    for x in (0..(width - 1)).rev() {
+        for y in 0..height {
+            let (mut best_index, mut min_cost) = (y, cost_next[y]);
+            if y > 0 && cost_next[y - 1] < min_cost {
+                best_index = y - 1;
+                min_cost = cost_next[y - 1]
+            }
+            if y < height - 1 && cost_next[y + 1] < min_cost {
+                best_index = y + 1;
+                min_cost = cost_next[y + 1]
+            }
+            path[y + x * height] = best_index;
+            cost[y] = energy[(x, y)] as u64 + min_cost;
         }
+        mem::swap(&mut cost, &mut cost_next);
     }
-    seam.push((0..width).min_by_key(|&x| cost[(x, 0)]).unwrap());
-    for y in 0..(height - 1) {
-        seam.push(path[(seam[y], y)])
+    seam.push((0..height).min_by_key(|&y| cost_next[y]).unwrap());
+    for x in 0..(width - 1) {
+        seam.push(path[seam[x] + x * height])
     }
     seam
 }
 
-pub fn insert_vertical_seams(img: &RgbImage, seams: &[Vec<usize>]) -> RgbImage {
+#[cfg(feature = "image")]
+pub fn insert_vertical_seams<P: Pixel<Subpixel = u8>>(
+    img: &ImageBuffer<P, Vec<u8>>,
+    seams: &[Vec<usize>],
+) -> ImageBuffer<P, Vec<u8>> {
     let (width, height) = img.dimensions();
     let to_insert = seams[0].len() as u32;
-    let mut new_img = RgbImage::new(width + to_insert, height);
+    let mut new_img = ImageBuffer::new(width + to_insert, height);
     let mut already_inserted = 0;
     for (y, to_insert_xs) in seams.iter().enumerate() {
         let mut to_insert_xs_sorted = to_insert_xs.clone();
@@ -67,10 +465,14 @@ pub fn insert_vertical_seams(img: &RgbImage, seams: &[Vec<usize>]) -> RgbImage {
     new_img
 }
 
-pub fn insert_horizontal_seams(img: &RgbImage, seams: &[Vec<usize>]) -> RgbImage {
+#[cfg(feature = "image")]
+pub fn insert_horizontal_seams<P: Pixel<Subpixel = u8>>(
+    img: &ImageBuffer<P, Vec<u8>>,
+    seams: &[Vec<usize>],
+) -> ImageBuffer<P, Vec<u8>> {
     let (width, height) = img.dimensions();
     let to_insert = seams[0].len() as u32;
-    let mut new_img = RgbImage::new(width, height + to_insert);
+    let mut new_img = ImageBuffer::new(width, height + to_insert);
     let mut already_inserted = 0;
     for (x, to_insert_ys) in seams.iter().enumerate() {
         let mut to_insert_ys_sorted = to_insert_ys.clone();
@@ -101,27 +503,62 @@ pub fn insert_horizontal_seams(img: &RgbImage, seams: &[Vec<usize>]) -> RgbImage
     new_img
 }
 
-fn avg_pixel<T: Pixel>(pixel_1: T, pixel_2: T) -> Rgb<u8> {
-    let (channels_1, channels_2) = (pixel_1.channels(), pixel_2.channels());
-    let new_channels = channels_1
-        .iter()
-        .zip(channels_2.iter())
-        .map(|(ch1, ch2)| avg_channel(ch1, ch2))
-        .collect::<Vec<u8>>();
-    Rgb([new_channels[0], new_channels[1], new_channels[2]])
+// adds `weight` per pixel of distance to each neighbour which used to be further apart,
+// i.e. with previously removed seams in between
+pub fn proximity_penalized(
+    energy: &Array2d<u32>,
+    positions: &Array2d<(u32, u32)>,
+    weight: u32,
+) -> Array2d<u32> {
+    let (width, height) = positions.dimensions();
+    let distance = |(x_1, y_1): (u32, u32), (x_2, y_2): (u32, u32)| -> u32 {
+        x_1.abs_diff(x_2) + y_1.abs_diff(y_2) - 1
+    };
+    let mut penalized = energy.clone();
+    for ((x, y), value) in penalized.iter_indexed_mut() {
+        let position = positions[(x, y)];
+        let mut gaps = 0;
+        if x > 0 {
+            gaps += distance(position, positions[(x - 1, y)]);
+        }
+        if x < width - 1 {
+            gaps += distance(position, positions[(x + 1, y)]);
+        }
+        if y > 0 {
+            gaps += distance(position, positions[(x, y - 1)]);
+        }
+        if y < height - 1 {
+            gaps += distance(position, positions[(x, y + 1)]);
+        }
+        *value = value.saturating_add(gaps.saturating_mul(weight));
+    }
+    penalized
+}
+
+// splitmix64 finalizer, so that seeded tie-breaking is reproducible across platforms
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
 }
 
-fn avg_channel<T: ToPrimitive>(channel_1: &T, channel_2: &T) -> u8 {
-    let channel_1 = channel_1.to_u8().unwrap_or(u8::MAX);
-    let channel_2 = channel_2.to_u8().unwrap_or(u8::MAX);
+#[cfg(feature = "image")]
+fn avg_pixel<P: Pixel<Subpixel = u8>>(pixel_1: P, pixel_2: P) -> P {
+    pixel_1.map2(&pixel_2, avg_channel)
+}
+
+#[cfg(feature = "image")]
+fn avg_channel(channel_1: u8, channel_2: u8) -> u8 {
     (channel_1 / 2) + (channel_2 / 2) + ((channel_1 % 2 + channel_2 % 2) / 2) // average without u8 overflow
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "image"))]
 mod tests {
     use super::*;
     use crate::array::positions_from_image;
     use crate::energy::get_energy_img;
+    use crate::test_utils::{fixture_img, gradient_img};
     use image::{Rgb, RgbImage};
 
     #[test]
@@ -162,4 +599,309 @@ mod tests {
         let seam = find_vertical_seam(&energy);
         assert_eq!(vec![3, 4, 3, 2, 2], seam);
     }
+
+    #[test]
+    fn horizontal_seam() {
+        let img = gradient_img(7, 6);
+        let positions = positions_from_image(&img).unwrap();
+        let mut energy = get_energy_img(&img, &positions).unwrap();
+        let seam = find_horizontal_seam(&energy);
+        energy.transpose();
+        assert_eq!(find_vertical_seam(&energy), seam);
+    }
+
+    #[test]
+    fn vertical_seams_disjoint() {
+        let img = gradient_img(9, 7);
+        let positions = positions_from_image(&img).unwrap();
+        let energy = get_energy_img(&img, &positions).unwrap();
+        let seams = find_vertical_seams(&energy, 4);
+        assert_eq!(4, seams.len());
+        assert_eq!(find_vertical_seam(&energy), seams[0]);
+        for y in 0..7 {
+            let mut row: Vec<usize> = seams.iter().map(|seam| seam[y]).collect();
+            row.sort_unstable();
+            row.dedup();
+            assert_eq!(4, row.len());
+        }
+        for seam in &seams {
+            for pair in seam.windows(2) {
+                assert!(pair[0].abs_diff(pair[1]) <= 1);
+            }
+        }
+    }
+
+    #[test]
+    fn vertical_seams_ordered() {
+        // the seam starting at 0 would cross the first one, going under it to reach 1 on the last
+        // row, and is dropped for the next cheapest start
+        let energy = Array2d::new(4, vec![4, 9, 2, 9, 1, 0, 2, 6, 1, 6, 4, 4]).unwrap();
+        let seams = find_vertical_seams(&energy, 2);
+        assert_eq!(vec![vec![2, 1, 0], vec![3, 2, 2]], seams);
+
+        for connectivity in 1..=3 {
+            let img = gradient_img(16, 11);
+            let positions = positions_from_image(&img).unwrap();
+            let energy = get_energy_img(&img, &positions).unwrap();
+            let options = SeamOptions {
+                connectivity,
+                ..SeamOptions::default()
+            };
+            let mut seams = find_vertical_seams_with(&energy, 6, &options);
+            assert_eq!(6, seams.len());
+            seams.sort_by_key(|seam| seam[0]);
+            for pair in seams.windows(2) {
+                assert!((0..11).all(|y| pair[0][y] < pair[1][y]));
+            }
+        }
+    }
+
+    #[test]
+    fn vertical_seams_exhausted() {
+        let energy = Array2d::new(2, vec![1, 2, 3, 4, 5, 6]).unwrap();
+        assert_eq!(
+            vec![vec![0, 0, 0], vec![1, 1, 1]],
+            find_vertical_seams(&energy, 5)
+        );
+    }
+
+    #[test]
+    fn vertical_seam_connectivity() {
+        let energy = Array2d::new(5, vec![0, 9, 9, 9, 9, 9, 9, 0, 9, 9, 9, 9, 9, 9, 0]).unwrap();
+        let steep = SeamOptions {
+            connectivity: 2,
+            ..SeamOptions::default()
+        };
+        assert_eq!(vec![0, 0, 0], find_vertical_seam(&energy));
+        assert_eq!(vec![0, 2, 4], find_vertical_seam_with(&energy, &steep));
+
+        let img = fixture_img();
+        let positions = positions_from_image(&img).unwrap();
+        let mut energy = get_energy_img(&img, &positions).unwrap();
+        let default = SeamOptions::default();
+        assert_eq!(
+            vec![3, 4, 3, 2, 2],
+            find_vertical_seam_with(&energy, &default)
+        );
+        let seam = find_vertical_seam_with(&energy, &steep);
+        let seam_cost =
+            |seam: &[usize]| -> u32 { seam.iter().enumerate().map(|(y, &x)| energy[(x, y)]).sum() };
+        assert!(seam_cost(&seam) <= seam_cost(&[3, 4, 3, 2, 2]));
+        for pair in seam.windows(2) {
+            assert!(pair[0].abs_diff(pair[1]) <= 2);
+        }
+        let horizontal = find_horizontal_seam_with(&energy, &steep);
+        energy.transpose();
+        assert_eq!(find_vertical_seam_with(&energy, &steep), horizontal);
+    }
+
+    #[test]
+    fn vertical_seam_strip() {
+        let energy = Array2d::new(3, vec![5, 0, 5, 0, 5, 5, 5, 5, 0, 5, 0, 5]).unwrap();
+        let strip = SeamOptions {
+            strip: 2,
+            ..SeamOptions::default()
+        };
+        assert_eq!(vec![1, 1, 2, 1], find_vertical_seam(&energy));
+        assert_eq!(vec![0, 0, 1, 1], find_vertical_seam_with(&energy, &strip));
+
+        let img = fixture_img();
+        let positions = positions_from_image(&img).unwrap();
+        let energy = get_energy_img(&img, &positions).unwrap();
+        let seam = find_vertical_seam_with(&energy, &strip);
+        assert_eq!(5, seam.len());
+        assert_eq!(seam[0], seam[1]);
+        assert_eq!(seam[2], seam[3]);
+        let seams = find_vertical_seams_with(&energy, 3, &strip);
+        assert_eq!(seam, seams[0]);
+        for seam in &seams {
+            assert_eq!(seam[0], seam[1]);
+            assert_eq!(seam[2], seam[3]);
+        }
+    }
+
+    #[test]
+    fn vertical_seam_tie_break() {
+        let energy = Array2d::new(7, vec![5; 7 * 4]).unwrap();
+        let with_tie_break = |tie_break| SeamOptions {
+            tie_break,
+            ..SeamOptions::default()
+        };
+        assert_eq!(vec![0; 4], find_vertical_seam(&energy));
+        let center = with_tie_break(TieBreak::Center);
+        assert_eq!(vec![3; 4], find_vertical_seam_with(&energy, &center));
+        let starts: Vec<usize> = find_vertical_seams_with(&energy, 7, &center)
+            .iter()
+            .map(|seam| seam[0])
+            .collect();
+        assert_eq!(vec![3, 2, 4, 1, 5, 0, 6], starts);
+
+        let seams: Vec<Vec<usize>> = (0..8)
+            .map(|seed| find_vertical_seam_with(&energy, &with_tie_break(TieBreak::Random(seed))))
+            .collect();
+        for (seed, seam) in seams.iter().enumerate() {
+            let random = with_tie_break(TieBreak::Random(seed as u64));
+            assert_eq!(*seam, find_vertical_seam_with(&energy, &random));
+            for pair in seam.windows(2) {
+                assert!(pair[0].abs_diff(pair[1]) <= 1);
+            }
+        }
+        assert!(seams.iter().any(|seam| *seam != seams[0]));
+    }
+
+    #[test]
+    fn tie_break_rank_overflow() {
+        let (x, width) = (usize::MAX - 1, usize::MAX);
+        let random = TieBreak::Random(7);
+        assert_eq!(
+            random.move_rank(x, x, x, width),
+            random.move_rank(x, x, x, width)
+        );
+        assert_ne!(
+            random.move_rank(x, x, x, width),
+            random.move_rank(x, x - 1, x, width)
+        );
+    }
+
+    #[test]
+    fn vertical_seam_tie_break_optimal() {
+        let img = fixture_img();
+        let positions = positions_from_image(&img).unwrap();
+        let energy = get_energy_img(&img, &positions).unwrap();
+        let seam_cost =
+            |seam: &[usize]| -> u32 { seam.iter().enumerate().map(|(y, &x)| energy[(x, y)]).sum() };
+        let leftmost = SeamOptions {
+            tie_break: TieBreak::Leftmost,
+            ..SeamOptions::default()
+        };
+        assert_eq!(
+            vec![3, 4, 3, 2, 2],
+            find_vertical_seam_with(&energy, &leftmost)
+        );
+        for tie_break in [TieBreak::Center, TieBreak::Random(42)] {
+            let options = SeamOptions {
+                tie_break,
+                ..SeamOptions::default()
+            };
+            let seam = find_vertical_seam_with(&energy, &options);
+            assert_eq!(seam_cost(&[3, 4, 3, 2, 2]), seam_cost(&seam));
+        }
+    }
+
+    #[test]
+    fn vertical_seam_zero_penalty() {
+        let img = fixture_img();
+        let mut positions = positions_from_image(&img).unwrap();
+        let energy = get_energy_img(&img, &positions).unwrap();
+        let options = SeamOptions {
+            diagonal_penalty: 0,
+            proximity_penalty: 0,
+            ..SeamOptions::default()
+        };
+        assert_eq!(
+            vec![3, 4, 3, 2, 2],
+            find_vertical_seam_with(&energy, &options)
+        );
+        assert_eq!(
+            find_vertical_seams(&energy, 3),
+            find_vertical_seams_with(&energy, 3, &options)
+        );
+        positions.remove_seam(&[3, 4, 3, 2, 2]).unwrap();
+        let mut energy = get_energy_img(&img, &positions).unwrap();
+        assert_eq!(energy, proximity_penalized(&energy, &positions, 0));
+        let horizontal = find_horizontal_seam(&energy);
+        energy.transpose();
+        assert_eq!(find_vertical_seam(&energy), horizontal);
+    }
+
+    #[test]
+    fn vertical_seam_diagonal_penalty() {
+        let img = fixture_img();
+        let positions = positions_from_image(&img).unwrap();
+        let energy = get_energy_img(&img, &positions).unwrap();
+        let options = SeamOptions {
+            diagonal_penalty: 1_000_000,
+            ..SeamOptions::default()
+        };
+        let seam = find_vertical_seam_with(&energy, &options);
+        assert!(seam.iter().all(|&x| x == seam[0]));
+        let seams = find_vertical_seams_with(&energy, 6, &options);
+        assert_eq!(6, seams.len());
+        for seam in &seams {
+            assert!(seam.iter().all(|&x| x == seam[0]));
+        }
+    }
+
+    #[test]
+    fn vertical_seam_proximity_penalty() {
+        let energy = Array2d::new(7, vec![5; 7 * 4]).unwrap();
+        let mut positions = positions_from_image(&RgbImage::new(8, 4)).unwrap();
+        positions.remove_seam(&[1, 1, 1, 1]).unwrap();
+        let penalized = proximity_penalized(&energy, &positions, 10);
+        assert_eq!(
+            vec![15, 15, 5, 5, 5, 5, 5],
+            penalized.rows().next().unwrap().to_vec()
+        );
+        assert_eq!(vec![0; 4], find_vertical_seam(&energy));
+        assert_eq!(vec![2; 4], find_vertical_seam(&penalized));
+    }
+
+    #[test]
+    fn vertical_seam_graph_cut() {
+        let img = fixture_img();
+        let positions = positions_from_image(&img).unwrap();
+        let energy = get_energy_img(&img, &positions).unwrap();
+        assert_eq!(vec![3, 4, 3, 2, 2], find_vertical_seam_graph_cut(&energy));
+
+        for (width, height) in [(1, 4), (5, 1), (9, 7), (16, 11)] {
+            let img = gradient_img(width, height);
+            let positions = positions_from_image(&img).unwrap();
+            let energy = get_energy_img(&img, &positions).unwrap();
+            let seam_cost = |seam: &[usize]| -> u64 {
+                seam.iter()
+                    .enumerate()
+                    .map(|(y, &x)| energy[(x, y)] as u64)
+                    .sum()
+            };
+            let seam = find_vertical_seam_graph_cut(&energy);
+            assert_eq!(height as usize, seam.len());
+            for pair in seam.windows(2) {
+                assert!(pair[0].abs_diff(pair[1]) <= 1);
+            }
+            assert_eq!(seam_cost(&find_vertical_seam(&energy)), seam_cost(&seam));
+        }
+    }
+
+    #[test]
+    fn vertical_seam_surface() {
+        let img = fixture_img();
+        let positions = positions_from_image(&img).unwrap();
+        let energy = get_energy_img(&img, &positions).unwrap();
+        let seams = find_vertical_seam_surface(&[energy.clone(), energy.clone()]).unwrap();
+        assert_eq!(vec![vec![3, 4, 3, 2, 2]; 2], seams);
+
+        let mut shifted = Array2d::new(6, vec![0; 30]).unwrap();
+        for ((x, y), value) in shifted.iter_indexed_mut() {
+            *value = energy[((x + 3) % 6, y)];
+        }
+        let seams = find_vertical_seam_surface(&[energy, shifted]).unwrap();
+        for (x_1, x_2) in seams[0].iter().zip(&seams[1]) {
+            assert!(x_1.abs_diff(*x_2) <= 1);
+        }
+    }
+
+    #[test]
+    fn vertical_seam_surface_incompatible() {
+        let frames = [
+            Array2d::new(3, vec![0; 6]).unwrap(),
+            Array2d::new(2, vec![0; 6]).unwrap(),
+        ];
+        assert_eq!(
+            Err(String::from(
+                "frame dimensions should be equal, got (2, 3) and (3, 2)"
+            )),
+            find_vertical_seam_surface(&frames).map_err(|e| format!("{}", e))
+        );
+        assert!(find_vertical_seam_surface(&[]).is_err());
+    }
 }
@@ -0,0 +1,223 @@
+use std::error::Error;
+
+use image::RgbImage;
+
+use crate::array::{self, Array2d};
+use crate::carver;
+use crate::energy::{self, PROTECTED_ENERGY};
+use crate::seam;
+
+pub const DEFAULT_BAND: usize = 2;
+
+type Positions = Array2d<(u32, u32)>;
+
+pub fn carve_sequence(
+    frames: &[RgbImage],
+    new_width: u32,
+    new_height: u32,
+) -> Result<Vec<RgbImage>, Box<dyn Error>> {
+    carve_sequence_with(frames, new_width, new_height, DEFAULT_BAND)
+}
+
+pub fn carve_sequence_with(
+    frames: &[RgbImage],
+    new_width: u32,
+    new_height: u32,
+    band: usize,
+) -> Result<Vec<RgbImage>, Box<dyn Error>> {
+    let (width, height) = match frames.first() {
+        Some(frame) => frame.dimensions(),
+        None => return Err("at least one frame is needed to carve a sequence".into()),
+    };
+    for frame in frames {
+        if frame.dimensions() != (width, height) {
+            return Err(format!(
+                "frame dimensions should be equal, got {:?} and {:?}",
+                frame.dimensions(),
+                (width, height)
+            )
+            .into());
+        }
+    }
+    carver::check_dimensions((width, height), (new_width, new_height))?;
+
+    let mut positions = all_positions(frames)?;
+    coherent_seams(
+        frames,
+        &mut positions,
+        width.saturating_sub(new_width),
+        band,
+        false,
+    )?;
+    positions
+        .iter_mut()
+        .for_each(|positions| positions.transpose());
+    coherent_seams(
+        frames,
+        &mut positions,
+        height.saturating_sub(new_height),
+        band,
+        true,
+    )?;
+    positions
+        .iter_mut()
+        .for_each(|positions| positions.transpose());
+    let mut frames_carved: Vec<RgbImage> = frames
+        .iter()
+        .zip(&positions)
+        .map(|(frame, positions)| array::filter_image_by_positions(frame, positions))
+        .collect();
+
+    let vertical_to_insert = new_width.saturating_sub(width);
+    if vertical_to_insert > 0 {
+        let mut positions = all_positions(&frames_carved)?;
+        let seams = coherent_seams(
+            &frames_carved,
+            &mut positions,
+            vertical_to_insert,
+            band,
+            false,
+        )?;
+        frames_carved = frames_carved
+            .iter()
+            .zip(&seams)
+            .map(|(frame, seams)| seam::insert_vertical_seams(frame, seams))
+            .collect();
+    }
+
+    let horizontal_to_insert = new_height.saturating_sub(height);
+    if horizontal_to_insert > 0 {
+        let mut positions = all_positions(&frames_carved)?;
+        positions
+            .iter_mut()
+            .for_each(|positions| positions.transpose());
+        let seams = coherent_seams(
+            &frames_carved,
+            &mut positions,
+            horizontal_to_insert,
+            band,
+            true,
+        )?;
+        frames_carved = frames_carved
+            .iter()
+            .zip(&seams)
+            .map(|(frame, seams)| seam::insert_horizontal_seams(frame, seams))
+            .collect();
+    }
+
+    Ok(frames_carved)
+}
+
+fn all_positions(frames: &[RgbImage]) -> Result<Vec<Positions>, Box<dyn Error>> {
+    frames.iter().map(array::positions_from_image).collect()
+}
+
+// removes seams from the positions of every frame, the i-th seam of a frame staying within
+// `band` pixels of the i-th seam of the previous one so that seams do not flicker; returns,
+// for each frame and row, the original coordinates of the removed pixels
+fn coherent_seams(
+    frames: &[RgbImage],
+    positions: &mut [Positions],
+    to_remove: u32,
+    band: usize,
+    horizontal: bool,
+) -> Result<Vec<Vec<Vec<usize>>>, Box<dyn Error>> {
+    let mut removed = Vec::with_capacity(frames.len());
+    let mut previous_seams: Vec<Vec<usize>> = Vec::new();
+    for (frame, positions) in frames.iter().zip(positions.iter_mut()) {
+        let mut energy_map = energy::get_energy_img(frame, positions)?;
+        let mut rows = vec![Vec::with_capacity(to_remove as usize); positions.height()];
+        let mut seams = Vec::with_capacity(to_remove as usize);
+        for i in 0..(to_remove as usize) {
+            let seam = match previous_seams.get(i) {
+                Some(previous) => seam::find_vertical_seam(&banded(&energy_map, previous, band)),
+                None => seam::find_vertical_seam(&energy_map),
+            };
+            for (y, &x) in seam.iter().enumerate() {
+                let (original_x, original_y) = positions[(x, y)];
+                rows[y].push(if horizontal { original_y } else { original_x } as usize);
+            }
+            positions.remove_seam(&seam)?;
+            energy::update_energy_img(&mut energy_map, frame, positions, &seam)?;
+            seams.push(seam);
+        }
+        previous_seams = seams;
+        removed.push(rows);
+    }
+    Ok(removed)
+}
+
+fn banded(energy_map: &Array2d<u32>, seam: &[usize], band: usize) -> Array2d<u32> {
+    let mut banded = energy_map.clone();
+    for (row, &center) in banded.rows_mut().zip(seam) {
+        let (first, last) = (center.saturating_sub(band), center + band);
+        for (x, value) in row.iter_mut().enumerate() {
+            if x < first || x > last {
+                *value = PROTECTED_ENERGY;
+            }
+        }
+    }
+    banded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::shifted_gradient_img;
+    use crate::Carver;
+
+    fn moving_frames(width: u32, height: u32, count: u32) -> Vec<RgbImage> {
+        (0..count)
+            .map(|t| shifted_gradient_img(width, height, t))
+            .collect()
+    }
+
+    #[test]
+    fn sequence_still() {
+        let frame = moving_frames(12, 10, 1).pop().unwrap();
+        let frames = vec![frame.clone(); 3];
+        for (new_width, new_height) in [(8, 7), (15, 13), (9, 12)] {
+            let expected = Carver::new(&frame).resize(new_width, new_height).unwrap();
+            for carved in carve_sequence(&frames, new_width, new_height).unwrap() {
+                assert_eq!(expected, carved);
+            }
+        }
+    }
+
+    #[test]
+    fn sequence_coherent() {
+        let frames = moving_frames(16, 10, 4);
+        let mut positions = all_positions(&frames).unwrap();
+        let removed = coherent_seams(&frames, &mut positions, 5, 1, false).unwrap();
+        for pair in removed.windows(2) {
+            // the first seam is removed before any other, so its coordinates are unchanged
+            for (row, next_row) in pair[0].iter().zip(&pair[1]) {
+                assert!(row[0].abs_diff(next_row[0]) <= 1);
+            }
+        }
+        let carved = carve_sequence(&frames, 11, 8).unwrap();
+        assert_eq!(4, carved.len());
+        assert!(carved.iter().all(|frame| frame.dimensions() == (11, 8)));
+    }
+
+    #[test]
+    fn sequence_incompatible() {
+        let frames = vec![RgbImage::new(4, 3), RgbImage::new(3, 4)];
+        assert_eq!(
+            Err(String::from(
+                "frame dimensions should be equal, got (3, 4) and (4, 3)"
+            )),
+            carve_sequence(&frames, 2, 2).map_err(|e| format!("{}", e))
+        );
+        assert_eq!(
+            Err(String::from(
+                "at least one frame is needed to carve a sequence"
+            )),
+            carve_sequence(&[], 2, 2).map_err(|e| format!("{}", e))
+        );
+        assert_eq!(
+            Err(String::from("new_width must be positive, got 0")),
+            carve_sequence(&frames[..1], 0, 2).map_err(|e| format!("{}", e))
+        );
+    }
+}
@@ -0,0 +1,341 @@
+use std::error::Error;
+use std::io::{Cursor, Read};
+use std::thread;
+
+use image::io::{Limits, Reader as ImageReader};
+use image::{GrayImage, ImageFormat, Luma};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::encode::{self, EncodeOptions};
+use crate::energy::{Border, Energy};
+use crate::metadata::{self, Metadata};
+use crate::parser::Dimension;
+use crate::Carver;
+
+pub const DEFAULT_ADDR: &str = "127.0.0.1:8080";
+pub const DEFAULT_MAX_BODY: usize = 16 << 20;
+pub const DEFAULT_MAX_DIMENSION: u32 = 8192;
+
+const USAGE: &str =
+    "Usage: rsc-server [--addr HOST:PORT] [--jobs N] [--max-body BYTES] [--max-dimension PIXELS]";
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ServerOptions {
+    pub workers: usize,
+    pub max_body: usize,
+    pub max_dimension: u32,
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        Self {
+            workers: thread::available_parallelism().map_or(1, |n| n.get()),
+            max_body: DEFAULT_MAX_BODY,
+            max_dimension: DEFAULT_MAX_DIMENSION,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct ServerConfig {
+    pub addr: String,
+    pub options: ServerOptions,
+}
+
+impl ServerConfig {
+    pub fn new(args: &[String]) -> Result<Self, Box<dyn Error>> {
+        let mut addr = String::from(DEFAULT_ADDR);
+        let mut options = ServerOptions::default();
+        let mut args_iter = args.iter().skip(1);
+        while let Some(arg) = args_iter.next() {
+            let mut value = || args_iter.next().ok_or(USAGE);
+            match arg.as_str() {
+                "-a" | "--addr" => addr = value()?.clone(),
+                "-j" | "--jobs" => options.workers = value()?.parse()?,
+                "--max-body" => options.max_body = value()?.parse()?,
+                "--max-dimension" => options.max_dimension = value()?.parse()?,
+                _ => return Err(USAGE.into()),
+            }
+        }
+        if options.workers == 0 {
+            return Err("jobs must be positive, got 0".into());
+        }
+        Ok(Self { addr, options })
+    }
+}
+
+// query parameters of a carving request, the image itself being the request body
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CarveParams {
+    pub width: Option<Dimension>,
+    pub height: Option<Dimension>,
+    pub energy: Energy,
+    pub border: Border,
+    pub protect: Vec<(u32, u32, u32, u32)>, // x, y, width and height of protected rectangles
+    pub format: Option<ImageFormat>,
+    pub encode: EncodeOptions,
+}
+
+impl CarveParams {
+    pub fn from_query(query: &str) -> Result<Self, Box<dyn Error>> {
+        let mut params = Self::default();
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(value)?;
+            match key {
+                "width" => params.width = Some(value.parse()?),
+                "height" => params.height = Some(value.parse()?),
+                "energy" => params.energy = value.parse()?,
+                "border" => params.border = value.parse()?,
+                "protect" => params.protect.push(parse_rectangle(&value)?),
+                "format" => params.format = Some(encode::parse_format(&value)?),
+                "quality" => params.encode.quality = encode::parse_quality(&value)?,
+                "compression" => params.encode.compression = encode::parse_compression(&value)?,
+                _ => return Err(format!("unknown query parameter {}", key).into()),
+            }
+        }
+        Ok(params)
+    }
+}
+
+// answers requests with a fixed pool of workers, each one carving a single image at a time
+#[cfg(not(tarpaulin_include))]
+pub fn serve(server: &Server, options: &ServerOptions) -> Result<(), Box<dyn Error>> {
+    crossbeam_utils::thread::scope(|s| {
+        for _ in 0..options.workers {
+            s.spawn(move |_| {
+                for request in server.incoming_requests() {
+                    respond(request, options);
+                }
+            });
+        }
+    })
+    .map_err(|_| "server worker thread panicked")?;
+    Ok(())
+}
+
+#[cfg(not(tarpaulin_include))]
+fn respond(mut request: Request, options: &ServerOptions) {
+    let response = match read_body(&mut request, options) {
+        Ok(body) => {
+            let query = request.url().split_once('?').map_or("", |(_, query)| query);
+            match carve_bytes(&body, query, options) {
+                Ok((bytes, format)) => {
+                    let mime = Header::from_bytes("Content-Type", format.to_mime_type()).unwrap();
+                    Response::from_data(bytes).with_header(mime)
+                }
+                Err(e) => Response::from_string(e.to_string()).with_status_code(400),
+            }
+        }
+        Err((status, message)) => Response::from_string(message).with_status_code(status),
+    };
+    // the client may have hung up already, there is nobody left to tell
+    let _ = request.respond(response);
+}
+
+#[cfg(not(tarpaulin_include))]
+fn read_body(request: &mut Request, options: &ServerOptions) -> Result<Vec<u8>, (u16, String)> {
+    if *request.method() != Method::Post {
+        return Err((405, String::from("only POST requests are supported")));
+    }
+    let too_large = (
+        413,
+        format!("request body must be at most {} bytes", options.max_body),
+    );
+    if request
+        .body_length()
+        .is_some_and(|length| length > options.max_body)
+    {
+        return Err(too_large);
+    }
+    // chunked bodies have no announced length, so reading stops right past the limit
+    let mut body = Vec::new();
+    request
+        .as_reader()
+        .take(options.max_body as u64 + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| (400, e.to_string()))?;
+    if body.len() > options.max_body {
+        return Err(too_large);
+    }
+    Ok(body)
+}
+
+// carves an encoded image as the query says, returning it encoded along with its format
+pub fn carve_bytes(
+    body: &[u8],
+    query: &str,
+    options: &ServerOptions,
+) -> Result<(Vec<u8>, ImageFormat), Box<dyn Error>> {
+    let params = CarveParams::from_query(query)?;
+    let mut reader = ImageReader::new(Cursor::new(body)).with_guessed_format()?;
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(options.max_dimension);
+    limits.max_image_height = Some(options.max_dimension);
+    reader.limits(limits);
+    let input_format = reader.format();
+    // metadata that cannot be parsed is dropped, without failing the request
+    let metadata = Metadata::from_bytes(body).unwrap_or_default();
+    let img = metadata::apply_orientation(reader.decode()?, metadata.orientation()).to_rgb8();
+
+    let (width, height) = img.dimensions();
+    let new_width = params
+        .width
+        .map_or(width, |new_width| new_width.resolve(width));
+    let new_height = params
+        .height
+        .map_or(height, |new_height| new_height.resolve(height));
+    if new_width > options.max_dimension || new_height > options.max_dimension {
+        return Err(format!(
+            "new dimensions must be at most {} pixels, got {}x{}",
+            options.max_dimension, new_width, new_height
+        )
+        .into());
+    }
+    let mut mask = GrayImage::new(width, height);
+    for &(x, y, w, h) in &params.protect {
+        for y in y..y.saturating_add(h).min(height) {
+            for x in x..x.saturating_add(w).min(width) {
+                mask.put_pixel(x, y, Luma([255]));
+            }
+        }
+    }
+    let mut carver = Carver::new(&img)
+        .energy(params.energy)
+        .border(params.border);
+    if !params.protect.is_empty() {
+        carver = carver.protect(&mask);
+    }
+    let img_carved = carver.resize(new_width, new_height)?;
+
+    let format = match params.format.or(input_format) {
+        Some(format) => format,
+        None => return Err("cannot guess the output format, use the format parameter".into()),
+    };
+    let encoded = encode::encode(&img_carved, format, &params.encode)?;
+    Ok((metadata.embed(encoded, (new_width, new_height))?, format))
+}
+
+fn parse_rectangle(s: &str) -> Result<(u32, u32, u32, u32), Box<dyn Error>> {
+    let values: Vec<&str> = s.split(',').collect();
+    match values[..] {
+        [x, y, w, h] => Ok((x.parse()?, y.parse()?, w.parse()?, h.parse()?)),
+        _ => Err(format!("protected rectangles must be given as x,y,w,h, got {}", s).into()),
+    }
+}
+
+fn percent_decode(s: &str) -> Result<String, Box<dyn Error>> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut chars = s.bytes();
+    while let Some(byte) = chars.next() {
+        bytes.push(match byte {
+            b'+' => b' ',
+            b'%' => {
+                let hex = [chars.next(), chars.next()];
+                let hex = match hex {
+                    [Some(high), Some(low)] => String::from_utf8(vec![high, low])?,
+                    _ => return Err(format!("invalid percent encoding, got {}", s).into()),
+                };
+                u8::from_str_radix(&hex, 16)?
+            }
+            byte => byte,
+        });
+    }
+    Ok(String::from_utf8(bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::gradient_img;
+
+    fn encoded_img(width: u32, height: u32) -> Vec<u8> {
+        let img = gradient_img(width, height);
+        encode::encode(&img, ImageFormat::Png, &EncodeOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn params_query() {
+        let params = CarveParams::from_query(
+            "width=50%25&height=7&energy=gradient&protect=1,2,3,4&format=jpg",
+        )
+        .unwrap();
+        assert_eq!(Some(Dimension::Percent(50)), params.width);
+        assert_eq!(Some(Dimension::Absolute(7)), params.height);
+        assert_eq!(Energy::Gradient, params.energy);
+        assert_eq!(vec![(1, 2, 3, 4)], params.protect);
+        assert_eq!(Some(ImageFormat::Jpeg), params.format);
+        assert_eq!(CarveParams::default(), CarveParams::from_query("").unwrap());
+        assert_eq!(
+            Err(String::from("unknown query parameter scale")),
+            CarveParams::from_query("scale=2").map_err(|e| format!("{}", e))
+        );
+        assert_eq!(
+            Err(String::from(
+                "protected rectangles must be given as x,y,w,h, got 1,2"
+            )),
+            CarveParams::from_query("protect=1%2C2").map_err(|e| format!("{}", e))
+        );
+    }
+
+    #[test]
+    fn carve_request() {
+        let options = ServerOptions::default();
+        let body = encoded_img(12, 10);
+        let (bytes, format) = carve_bytes(&body, "width=8&protect=0,0,4,10", &options).unwrap();
+        assert_eq!(ImageFormat::Png, format);
+        let carved = image::load_from_memory(&bytes).unwrap().to_rgb8();
+        assert_eq!((8, 10), carved.dimensions());
+        let original = image::load_from_memory(&body).unwrap().to_rgb8();
+        for y in 0..10 {
+            for x in 0..4 {
+                assert_eq!(original.get_pixel(x, y), carved.get_pixel(x, y));
+            }
+        }
+
+        // metadata img-parts cannot parse is dropped, the image being carved anyway
+        let mut body = body;
+        *body.last_mut().unwrap() ^= 0xff;
+        let (bytes, _) = carve_bytes(&body, "width=8", &options).unwrap();
+        let carved = image::load_from_memory(&bytes).unwrap().to_rgb8();
+        assert_eq!((8, 10), carved.dimensions());
+    }
+
+    #[test]
+    fn carve_request_limits() {
+        let options = ServerOptions {
+            max_dimension: 11,
+            ..ServerOptions::default()
+        };
+        assert!(carve_bytes(&encoded_img(12, 10), "", &options).is_err());
+        assert_eq!(
+            Err(String::from(
+                "new dimensions must be at most 11 pixels, got 12x10"
+            )),
+            carve_bytes(&encoded_img(10, 10), "width=12", &options)
+                .map(|_| ())
+                .map_err(|e| format!("{}", e))
+        );
+    }
+
+    #[test]
+    fn server_config() {
+        let args = [
+            String::from("rsc-server"),
+            String::from("--addr"),
+            String::from("0.0.0.0:3000"),
+            String::from("-j"),
+            String::from("2"),
+            String::from("--max-body"),
+            String::from("1024"),
+        ];
+        let config = ServerConfig::new(&args).unwrap();
+        assert_eq!("0.0.0.0:3000", config.addr);
+        assert_eq!(2, config.options.workers);
+        assert_eq!(1024, config.options.max_body);
+        assert_eq!(
+            Err(String::from(USAGE)),
+            ServerConfig::new(&args[..2]).map_err(|e| format!("{}", e))
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use std::error::Error;
+
+use image::RgbImage;
+
+use crate::array::{self, Array2d};
+use crate::energy::{self, EnergyOptions};
+use crate::seam::{self, SeamOptions};
+
+struct RemovedSeam {
+    seam: Vec<usize>,
+    positions: Vec<(u32, u32)>,
+}
+
+pub struct Session<'a> {
+    img: &'a RgbImage,
+    options: EnergyOptions,
+    seam_options: SeamOptions,
+    positions: Array2d<(u32, u32)>,
+    energy_map: Array2d<u32>,
+    removed: Vec<RemovedSeam>,
+}
+
+impl<'a> Session<'a> {
+    pub fn new(img: &'a RgbImage) -> Result<Self, Box<dyn Error>> {
+        Self::with_options(img, EnergyOptions::default())
+    }
+
+    pub fn with_options(img: &'a RgbImage, options: EnergyOptions) -> Result<Self, Box<dyn Error>> {
+        let positions = array::positions_from_image(img)?;
+        let energy_map = energy::get_energy_img_with(img, &positions, &options)?;
+        Ok(Self::from_parts(
+            img,
+            options,
+            SeamOptions::default(),
+            positions,
+            energy_map,
+        ))
+    }
+
+    pub(crate) fn from_parts(
+        img: &'a RgbImage,
+        options: EnergyOptions,
+        seam_options: SeamOptions,
+        positions: Array2d<(u32, u32)>,
+        energy_map: Array2d<u32>,
+    ) -> Self {
+        Self {
+            img,
+            options,
+            seam_options,
+            positions,
+            energy_map,
+            removed: Vec::new(),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.positions.width() as u32
+    }
+
+    pub fn height(&self) -> u32 {
+        self.positions.height() as u32
+    }
+
+    pub fn removed(&self) -> usize {
+        self.removed.len()
+    }
+
+    pub fn positions(&self) -> &Array2d<(u32, u32)> {
+        &self.positions
+    }
+
+    pub fn energy_map(&self) -> &Array2d<u32> {
+        &self.energy_map
+    }
+
+    pub fn remove_seam(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.positions.width() <= 1 {
+            return Err("cannot remove a seam from an image of width 1".into());
+        }
+        // previously removed seams are penalized on a copy, as when carving
+        let seam = match self.seam_options.proximity_penalty {
+            0 => seam::find_vertical_seam_with(&self.energy_map, &self.seam_options),
+            weight => {
+                let energy_map =
+                    seam::proximity_penalized(&self.energy_map, &self.positions, weight);
+                seam::find_vertical_seam_with(&energy_map, &self.seam_options)
+            }
+        };
+        let positions = seam
+            .iter()
+            .enumerate()
+            .map(|(y, &x)| self.positions[(x, y)])
+            .collect();
+        self.positions.remove_seam(&seam)?;
+        energy::update_energy_img_with(
+            &mut self.energy_map,
+            self.img,
+            &self.positions,
+            &seam,
+            &self.options,
+        )?;
+        self.removed.push(RemovedSeam { seam, positions });
+        Ok(())
+    }
+
+    pub fn restore_seam(&mut self) -> Result<bool, Box<dyn Error>> {
+        let removed = match self.removed.pop() {
+            Some(removed) => removed,
+            None => return Ok(false),
+        };
+        self.positions
+            .insert_seam(&removed.seam, &removed.positions)?;
+        energy::restore_energy_img_with(
+            &mut self.energy_map,
+            self.img,
+            &self.positions,
+            &removed.seam,
+            &self.options,
+        )?;
+        Ok(true)
+    }
+
+    pub fn set_width(&mut self, new_width: u32) -> Result<(), Box<dyn Error>> {
+        let original_width = self.width() + self.removed() as u32;
+        if new_width == 0 || new_width > original_width {
+            return Err(format!(
+                "new_width must be between 1 and the original width, got {} and {}",
+                new_width, original_width
+            )
+            .into());
+        }
+        while self.width() > new_width {
+            self.remove_seam()?;
+        }
+        while self.width() < new_width {
+            self.restore_seam()?;
+        }
+        Ok(())
+    }
+
+    pub fn image(&self) -> RgbImage {
+        array::filter_image_by_positions(self.img, &self.positions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{gradient_img, shifted_gradient_img};
+    use crate::Carver;
+
+    #[test]
+    fn remove_restore() {
+        let img = gradient_img(12, 10);
+        let mut session = Session::new(&img).unwrap();
+        let positions = session.positions().clone();
+        let energy_map = session.energy_map().clone();
+        for _ in 0..5 {
+            session.remove_seam().unwrap();
+        }
+        assert_eq!(7, session.width());
+        for _ in 0..5 {
+            assert!(session.restore_seam().unwrap());
+        }
+        assert!(!session.restore_seam().unwrap());
+        assert_eq!(&positions, session.positions());
+        assert_eq!(&energy_map, session.energy_map());
+        assert_eq!(img, session.image());
+    }
+
+    #[test]
+    fn remove_restore_connectivity() {
+        let img = gradient_img(12, 10);
+        let mut session = Carver::new(&img).connectivity(3).into_session().unwrap();
+        let positions = session.positions().clone();
+        let energy_map = session.energy_map().clone();
+        for _ in 0..5 {
+            session.remove_seam().unwrap();
+            // the energy kept up to date matches the one computed from scratch
+            let energy = energy::get_energy_img(&img, session.positions()).unwrap();
+            assert_eq!(&energy, session.energy_map());
+        }
+        assert_eq!(
+            Carver::new(&img).connectivity(3).resize(7, 10).unwrap(),
+            session.image()
+        );
+        for _ in 0..5 {
+            assert!(session.restore_seam().unwrap());
+            let energy = energy::get_energy_img(&img, session.positions()).unwrap();
+            assert_eq!(&energy, session.energy_map());
+        }
+        assert_eq!(&positions, session.positions());
+        assert_eq!(&energy_map, session.energy_map());
+    }
+
+    #[test]
+    fn remove_proximity_penalty() {
+        // an image on which the penalty moves the seams
+        let img = shifted_gradient_img(16, 10, 7);
+        let carver = || Carver::new(&img).proximity_penalty(500);
+        let mut session = carver().into_session().unwrap();
+        for _ in 0..6 {
+            session.remove_seam().unwrap();
+        }
+        assert_eq!(carver().resize(10, 10).unwrap(), session.image());
+        assert_ne!(Carver::new(&img).resize(10, 10).unwrap(), session.image());
+    }
+
+    #[test]
+    fn set_width() {
+        let img = gradient_img(12, 10);
+        let mut session = Session::new(&img).unwrap();
+        session.set_width(6).unwrap();
+        session.set_width(9).unwrap();
+        assert_eq!(Carver::new(&img).resize(9, 10).unwrap(), session.image());
+        session.set_width(7).unwrap();
+        assert_eq!(Carver::new(&img).resize(7, 10).unwrap(), session.image());
+    }
+
+    #[test]
+    fn set_width_invalid() {
+        let img = gradient_img(12, 10);
+        let mut session = Session::new(&img).unwrap();
+        session.set_width(8).unwrap();
+        assert_eq!(
+            Err(String::from(
+                "new_width must be between 1 and the original width, got 13 and 12"
+            )),
+            session.set_width(13).map_err(|e| format!("{}", e))
+        );
+    }
+
+    #[test]
+    fn remove_width_one() {
+        let img = gradient_img(2, 3);
+        let mut session = Session::new(&img).unwrap();
+        session.remove_seam().unwrap();
+        assert_eq!(
+            Err(String::from(
+                "cannot remove a seam from an image of width 1"
+            )),
+            session.remove_seam().map_err(|e| format!("{}", e))
+        );
+    }
+}
@@ -0,0 +1,231 @@
+use std::error::Error;
+
+use image::{DynamicImage, RgbImage};
+
+use crate::array::{self, Array2d};
+use crate::energy;
+use crate::seam;
+
+type Positions = Array2d<(u32, u32)>;
+
+// a pixel (x, y) of the left view shows the same point as the pixel (x - disparity, y) of the
+// right view
+pub fn disparity_from_image(img: &DynamicImage) -> Result<Array2d<i32>, Box<dyn Error>> {
+    let width = img.width() as usize;
+    match img {
+        DynamicImage::ImageLuma16(img) => {
+            Array2d::new(width, img.pixels().map(|p| p.0[0] as i32).collect())
+        }
+        _ => Array2d::new(
+            width,
+            img.to_luma8().pixels().map(|p| p.0[0] as i32).collect(),
+        ),
+    }
+}
+
+pub fn carve_stereo(
+    left: &RgbImage,
+    right: &RgbImage,
+    disparity: &Array2d<i32>,
+    new_width: u32,
+) -> Result<(RgbImage, RgbImage), Box<dyn Error>> {
+    let (left_positions, right_positions) = stereo_positions(left, right, disparity, new_width)?;
+    Ok((
+        array::filter_image_by_positions(left, &left_positions),
+        array::filter_image_by_positions(right, &right_positions),
+    ))
+}
+
+fn stereo_positions(
+    left: &RgbImage,
+    right: &RgbImage,
+    disparity: &Array2d<i32>,
+    new_width: u32,
+) -> Result<(Positions, Positions), Box<dyn Error>> {
+    let (width, height) = left.dimensions();
+    let disparity_dimensions = (disparity.width() as u32, disparity.height() as u32);
+    if right.dimensions() != (width, height) || disparity_dimensions != (width, height) {
+        return Err(format!(
+            "stereo images and disparity map dimensions should be equal, got {:?}, {:?} and {:?}",
+            left.dimensions(),
+            right.dimensions(),
+            disparity_dimensions
+        )
+        .into());
+    }
+    if new_width == 0 || new_width > width {
+        return Err(format!(
+            "new_width must be between 1 and the original width, got {} and {}",
+            new_width, width
+        )
+        .into());
+    }
+
+    let mut left_positions = array::positions_from_image(left)?;
+    let mut right_positions = array::positions_from_image(right)?;
+    let mut left_energy = energy::get_energy_img(left, &left_positions)?;
+    let mut right_energy = energy::get_energy_img(right, &right_positions)?;
+    for _ in 0..(width - new_width) {
+        // seams are searched in the left view, paying for the matched pixels of both views
+        let matches = match_right(&left_positions, &right_positions, disparity);
+        let mut combined = left_energy.clone();
+        for ((x, y), value) in combined.iter_indexed_mut() {
+            *value = value.saturating_add(right_energy[(matches[(x, y)], y)]);
+        }
+        let left_seam = seam::find_vertical_seam(&combined);
+        let right_seam: Vec<usize> = left_seam
+            .iter()
+            .enumerate()
+            .map(|(y, &x)| matches[(x, y)])
+            .collect();
+        left_positions.remove_seam(&left_seam)?;
+        energy::update_energy_img(&mut left_energy, left, &left_positions, &left_seam)?;
+        right_positions.remove_seam(&right_seam)?;
+        energy::update_energy_img(&mut right_energy, right, &right_positions, &right_seam)?;
+    }
+    Ok((left_positions, right_positions))
+}
+
+// index in the right view of the pixel matched by each pixel of the left view; pixels which
+// are occluded in the right view, or whose match was removed already, are matched with the
+// closest right pixel which no other left pixel sees, so that removing them keeps the
+// remaining pixels matched
+fn match_right(
+    left_positions: &Positions,
+    right_positions: &Positions,
+    disparity: &Array2d<i32>,
+) -> Array2d<usize> {
+    let (width, height) = left_positions.dimensions();
+    let original_width = disparity.width() as i64;
+    let mut matches = Vec::with_capacity(width * height);
+    for (left_row, right_row) in left_positions.rows().zip(right_positions.rows()) {
+        let targets: Vec<(usize, (bool, i32))> = left_row
+            .iter()
+            .map(|&(x, y)| {
+                let shift = disparity[(x as usize, y as usize)];
+                let target = (x as i64 - shift as i64).clamp(0, original_width - 1) as u32;
+                let index = nearest(
+                    right_row.partition_point(|&(right_x, _)| right_x < target),
+                    width,
+                    |i| right_row[i].0.abs_diff(target),
+                );
+                (index, (right_row[index].0 == target, shift))
+            })
+            .collect();
+        // exact matches win over approximate ones, then the closest point wins
+        let mut owners = vec![None; width];
+        for (x, &(index, priority)) in targets.iter().enumerate() {
+            if owners[index].is_none_or(|owner: usize| priority >= targets[owner].1) {
+                owners[index] = Some(x);
+            }
+        }
+        let free: Vec<usize> = (0..width).filter(|&i| owners[i].is_none()).collect();
+        for (x, &(index, _)) in targets.iter().enumerate() {
+            if owners[index] == Some(x) || free.is_empty() {
+                matches.push(index);
+            } else {
+                let after = free.partition_point(|&i| i < index);
+                matches.push(free[nearest(after, free.len(), |i| free[i].abs_diff(index))]);
+            }
+        }
+    }
+    Array2d::new(width, matches).unwrap()
+}
+
+// picks between the elements around a partition point the closest one, the first on ties
+fn nearest<D: Ord, F: Fn(usize) -> D>(after: usize, len: usize, distance: F) -> usize {
+    match after {
+        0 => 0,
+        after if after == len => len - 1,
+        after if distance(after - 1) <= distance(after) => after - 1,
+        after => after,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{gradient_img, shifted_gradient_img};
+    use crate::Carver;
+    use image::{GrayImage, ImageBuffer, Luma};
+
+    #[test]
+    fn stereo_same_view() {
+        let img = gradient_img(12, 10);
+        let disparity = Array2d::new(12, vec![0; 120]).unwrap();
+        let expected = Carver::new(&img).resize(7, 10).unwrap();
+        let (left, right) = carve_stereo(&img, &img, &disparity, 7).unwrap();
+        assert_eq!(expected, left);
+        assert_eq!(expected, right);
+    }
+
+    #[test]
+    fn stereo_shared_seams() {
+        let (left, right) = (gradient_img(12, 10), shifted_gradient_img(12, 10, 5));
+        let disparity = Array2d::new(12, vec![0; 120]).unwrap();
+        let (left_positions, right_positions) =
+            stereo_positions(&left, &right, &disparity, 8).unwrap();
+        assert_eq!(left_positions, right_positions);
+        assert_eq!((8, 10), left_positions.dimensions());
+    }
+
+    #[test]
+    fn stereo_shifted_view() {
+        // the right view sees the scene two pixels further left
+        let left = gradient_img(12, 10);
+        let right = RgbImage::from_fn(12, 10, |x, y| *left.get_pixel((x + 2).min(11), y));
+        let disparity = Array2d::new(12, vec![2; 120]).unwrap();
+        let (left_positions, right_positions) =
+            stereo_positions(&left, &right, &disparity, 9).unwrap();
+        let (left_carved, right_carved) = carve_stereo(&left, &right, &disparity, 9).unwrap();
+        assert_eq!((9, 10), left_carved.dimensions());
+        assert_eq!((9, 10), right_carved.dimensions());
+        let matches = match_right(&left_positions, &right_positions, &disparity);
+        for ((x, y), &(original_x, _)) in left_positions.iter_indexed() {
+            if original_x >= 2 {
+                assert_eq!(original_x - 2, right_positions[(matches[(x, y)], y)].0);
+            }
+        }
+    }
+
+    #[test]
+    fn match_removed() {
+        let disparity = Array2d::new(8, vec![2; 8]).unwrap();
+        let mut left_positions = array::positions_from_image(&RgbImage::new(8, 1)).unwrap();
+        let mut right_positions = left_positions.clone();
+        left_positions.remove_seam(&[7]).unwrap();
+        right_positions.remove_seam(&[4]).unwrap();
+        let matches = match_right(&left_positions, &right_positions, &disparity);
+        assert_eq!(vec![4, 4, 0, 1, 2, 3, 4], matches.raw_data().to_vec());
+    }
+
+    #[test]
+    fn disparity_png() {
+        let gray = GrayImage::from_fn(3, 2, |x, y| Luma([(x + 3 * y) as u8]));
+        let disparity = disparity_from_image(&DynamicImage::ImageLuma8(gray)).unwrap();
+        assert_eq!(vec![0, 1, 2, 3, 4, 5], disparity.raw_data().to_vec());
+        let deep: ImageBuffer<Luma<u16>, Vec<u16>> =
+            ImageBuffer::from_fn(2, 1, |x, _| Luma([300 * x as u16]));
+        let disparity = disparity_from_image(&DynamicImage::ImageLuma16(deep)).unwrap();
+        assert_eq!(vec![0, 300], disparity.raw_data().to_vec());
+    }
+
+    #[test]
+    fn stereo_incompatible() {
+        let img = gradient_img(4, 3);
+        let disparity = Array2d::new(3, vec![0; 12]).unwrap();
+        assert_eq!(
+            Err(String::from(
+                "stereo images and disparity map dimensions should be equal, got (4, 3), (4, 3) and (3, 4)"
+            )),
+            carve_stereo(&img, &img, &disparity, 2).map_err(|e| format!("{}", e))
+        );
+        let disparity = Array2d::new(4, vec![0; 12]).unwrap();
+        assert_eq!(
+            Err(String::from(
+                "new_width must be between 1 and the original width, got 5 and 4"
+            )),
+            carve_stereo(&img, &img, &disparity, 5).map_err(|e| format!("{}", e))
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use image::{Rgb, RgbImage};
+
+// smooth enough for seams to differ from one image to the next, without any flat area
+pub(crate) fn gradient_img(width: u32, height: u32) -> RgbImage {
+    shifted_gradient_img(width, height, 0)
+}
+
+// the gradient moved `shift` pixels to the left, e.g. for frames of a panning clip
+pub(crate) fn shifted_gradient_img(width: u32, height: u32, shift: u32) -> RgbImage {
+    RgbImage::from_fn(width, height, |x, y| {
+        let x = x + shift;
+        Rgb([
+            (x * 37 % 256) as u8,
+            (y * 59 % 256) as u8,
+            ((x * y) % 256) as u8,
+        ])
+    })
+}
+
+// the 6x5 image of the baseline seam and energy tests
+pub(crate) fn fixture_img() -> RgbImage {
+    let mut img = RgbImage::new(6, 5);
+    img.put_pixel(0, 0, Rgb([78, 209, 79]));
+    img.put_pixel(1, 0, Rgb([63, 118, 247]));
+    img.put_pixel(2, 0, Rgb([92, 175, 95]));
+    img.put_pixel(3, 0, Rgb([243, 73, 183]));
+    img.put_pixel(4, 0, Rgb([210, 109, 104]));
+    img.put_pixel(5, 0, Rgb([252, 101, 119]));
+    img.put_pixel(0, 1, Rgb([224, 191, 182]));
+    img.put_pixel(1, 1, Rgb([108, 89, 82]));
+    img.put_pixel(2, 1, Rgb([80, 196, 230]));
+    img.put_pixel(3, 1, Rgb([112, 156, 180]));
+    img.put_pixel(4, 1, Rgb([176, 178, 120]));
+    img.put_pixel(5, 1, Rgb([142, 151, 142]));
+    img.put_pixel(0, 2, Rgb([117, 189, 149]));
+    img.put_pixel(1, 2, Rgb([171, 231, 153]));
+    img.put_pixel(2, 2, Rgb([149, 164, 168]));
+    img.put_pixel(3, 2, Rgb([107, 119, 71]));
+    img.put_pixel(4, 2, Rgb([120, 105, 138]));
+    img.put_pixel(5, 2, Rgb([163, 174, 196]));
+    img.put_pixel(0, 3, Rgb([163, 222, 132]));
+    img.put_pixel(1, 3, Rgb([187, 117, 183]));
+    img.put_pixel(2, 3, Rgb([92, 145, 69]));
+    img.put_pixel(3, 3, Rgb([158, 143, 79]));
+    img.put_pixel(4, 3, Rgb([220, 75, 222]));
+    img.put_pixel(5, 3, Rgb([189, 73, 214]));
+    img.put_pixel(0, 4, Rgb([211, 120, 173]));
+    img.put_pixel(1, 4, Rgb([188, 218, 244]));
+    img.put_pixel(2, 4, Rgb([214, 103, 68]));
+    img.put_pixel(3, 4, Rgb([163, 166, 246]));
+    img.put_pixel(4, 4, Rgb([79, 125, 246]));
+    img.put_pixel(5, 4, Rgb([211, 201, 98]));
+    img
+}
@@ -0,0 +1,103 @@
+use std::error::Error;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::Clamped;
+
+use crate::carver::Options;
+use crate::raw::{self, Layout, RawFrame};
+
+#[wasm_bindgen]
+pub struct CarvedImage {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl CarvedImage {
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    // rgba pixels, ready for `new ImageData(carved.data, carved.width, carved.height)`
+    #[wasm_bindgen(getter)]
+    pub fn data(&self) -> Clamped<Vec<u8>> {
+        Clamped(self.data.clone())
+    }
+}
+
+// takes the `data`, `width` and `height` of a canvas `ImageData`
+#[wasm_bindgen(js_name = seamcarve)]
+pub fn seamcarve_image_data(
+    data: Clamped<Vec<u8>>,
+    width: u32,
+    height: u32,
+    new_width: u32,
+    new_height: u32,
+) -> Result<CarvedImage, JsError> {
+    let data = carve_rgba(&data, width, height, new_width, new_height)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(CarvedImage {
+        width: new_width,
+        height: new_height,
+        data,
+    })
+}
+
+// seams are found on the colors, the alpha channel following them
+fn carve_rgba(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    new_width: u32,
+    new_height: u32,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let size = width as usize * height as usize * 4;
+    if data.len() != size {
+        return Err(format!(
+            "rgba data should hold {} bytes for {}x{} pixels, got {}",
+            size,
+            width,
+            height,
+            data.len()
+        )
+        .into());
+    }
+    let frame = RawFrame::packed(data, width, height, Layout::Rgba)?;
+    raw::seamcarve_raw(&frame, new_width, new_height, &Options::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::gradient_img;
+    use crate::Carver;
+
+    #[test]
+    fn carve_rgba_data() {
+        let img = gradient_img(12, 10);
+        let data: Vec<u8> = img
+            .pixels()
+            .flat_map(|pixel| [pixel.0[0], pixel.0[1], pixel.0[2], 255])
+            .collect();
+        let carved = carve_rgba(&data, 12, 10, 8, 11).unwrap();
+        let expected = Carver::new(&img).resize(8, 11).unwrap();
+        let expected: Vec<u8> = expected
+            .pixels()
+            .flat_map(|pixel| [pixel.0[0], pixel.0[1], pixel.0[2], 255])
+            .collect();
+        assert_eq!(expected, carved);
+        assert_eq!(
+            Err(String::from(
+                "rgba data should hold 480 bytes for 12x10 pixels, got 360"
+            )),
+            carve_rgba(&data[..360], 12, 10, 8, 11).map_err(|e| format!("{}", e))
+        );
+    }
+}
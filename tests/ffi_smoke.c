@@ -0,0 +1,55 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "rsc.h"
+
+#define WIDTH 12
+#define HEIGHT 10
+#define STRIDE 64
+
+int main(void) {
+  uint8_t rgba[STRIDE * HEIGHT];
+  memset(rgba, 0, sizeof(rgba));
+  for (uint32_t y = 0; y < HEIGHT; y++) {
+    for (uint32_t x = 0; x < WIDTH; x++) {
+      uint8_t *pixel = rgba + y * STRIDE + x * 4;
+      pixel[0] = (uint8_t)(x * 37);
+      pixel[1] = (uint8_t)(y * 59);
+      pixel[2] = (uint8_t)(x * y);
+      pixel[3] = 255;
+    }
+  }
+
+  RscOptions options = rsc_default_options();
+  options.border = RSC_BORDER_CLAMP;
+  RscImage out;
+  RscStatus status = rsc_carve(rgba, WIDTH, HEIGHT, STRIDE, 8, 11, &options, &out);
+  if (status != RSC_STATUS_OK) {
+    fprintf(stderr, "rsc_carve failed: %s\n", rsc_last_error());
+    return 1;
+  }
+  if (out.width != 8 || out.height != 11 || out.stride != 32 || out.len != 8 * 11 * 4) {
+    fprintf(stderr, "unexpected output of %ux%u pixels\n", out.width, out.height);
+    return 1;
+  }
+  for (size_t i = 3; i < out.len; i += 4) {
+    if (out.data[i] != 255) {
+      fprintf(stderr, "unexpected alpha at byte %zu\n", i);
+      return 1;
+    }
+  }
+  rsc_image_free(&out);
+  if (out.data != NULL) {
+    fprintf(stderr, "rsc_image_free did not reset the image\n");
+    return 1;
+  }
+
+  options.order = 7;
+  status = rsc_carve(rgba, WIDTH, HEIGHT, STRIDE, 8, 11, &options, &out);
+  if (status != RSC_STATUS_INVALID_OPTION || strcmp(rsc_last_error(), "unknown order, got 7")) {
+    fprintf(stderr, "unexpected error %d: %s\n", status, rsc_last_error());
+    return 1;
+  }
+  return 0;
+}
@@ -1,6 +1,7 @@
 use image::io::Reader as ImageReader;
 use image::{GrayImage, Rgb};
 
+#[allow(clippy::single_component_path_imports)]
 use rsc;
 
 const SCALING: f64 = 2000f64;
@@ -220,3 +221,236 @@ fn insert_both() {
         .unwrap();
     assert_eq!((new_width, new_height), img_carved.dimensions());
 }
+
+#[test]
+#[ignore]
+fn carve_width_fast() {
+    // removing several disjoint seams per pass trades seam quality for speed:
+    // the removed pixels carry more of the original energy than with exact carving
+    let img_original = ImageReader::open("./img/Broadway_tower_edit.jpg")
+        .unwrap()
+        .decode()
+        .unwrap()
+        .to_rgb8();
+    let (width, height) = img_original.dimensions();
+    let new_width = 957;
+    let positions = rsc::array::positions_from_image(&img_original).unwrap();
+    let energy_original = rsc::energy::get_energy_img(&img_original, &positions).unwrap();
+    let removed_energy = |kept_positions: &rsc::array::Array2d<(u32, u32)>| {
+        let total: u64 = energy_original.raw_data().iter().map(|&e| e as u64).sum();
+        let kept: u64 = kept_positions
+            .raw_data()
+            .iter()
+            .map(|&(x, y)| energy_original[(x as usize, y as usize)] as u64)
+            .sum();
+        total - kept
+    };
+
+    let mut exact_positions = positions.clone();
+    let mut energy_map = energy_original.clone();
+    let start = std::time::Instant::now();
+    for _ in 0..(width - new_width) {
+        let seam = rsc::seam::find_vertical_seam(&energy_map);
+        exact_positions.remove_seam(&seam).unwrap();
+        rsc::energy::update_energy_img(&mut energy_map, &img_original, &exact_positions, &seam)
+            .unwrap();
+    }
+    let exact_time = start.elapsed();
+
+    let mut fast_positions = positions.clone();
+    let mut energy_map = energy_original.clone();
+    let mut removed = 0;
+    let start = std::time::Instant::now();
+    while removed < width - new_width {
+        let to_find = 16.min(width - new_width - removed) as usize;
+        let seams = rsc::seam::find_vertical_seams(&energy_map, to_find);
+        fast_positions.remove_seams(&seams).unwrap();
+        energy_map = rsc::energy::get_energy_img(&img_original, &fast_positions).unwrap();
+        removed += seams.len() as u32;
+    }
+    let fast_time = start.elapsed();
+
+    println!(
+        "exact: {:?}, removed energy {}",
+        exact_time,
+        removed_energy(&exact_positions)
+    );
+    println!(
+        "fast (16 seams per pass): {:?}, removed energy {}",
+        fast_time,
+        removed_energy(&fast_positions)
+    );
+    assert!(removed_energy(&exact_positions) <= removed_energy(&fast_positions));
+
+    let img_carved = rsc::Carver::new(&img_original)
+        .seams_per_pass(16)
+        .resize(new_width, height)
+        .unwrap();
+    img_carved
+        .save("./img/Broadway_tower_edit_carved_width_fast.jpg")
+        .unwrap();
+    assert_eq!((new_width, height), img_carved.dimensions());
+}
+
+#[cfg(feature = "fs")]
+#[test]
+fn strip_metadata_rotated() {
+    // a 12x8 jpeg stored sideways, i.e. displayed as 8x12 by its exif orientation 6
+    let img = image::RgbImage::from_fn(12, 8, |x, y| Rgb([(x * 20) as u8, (y * 30) as u8, 0]));
+    let mut jpeg = std::io::Cursor::new(Vec::new());
+    img.write_to(&mut jpeg, image::ImageOutputFormat::Jpeg(90))
+        .unwrap();
+    let mut exif = b"Exif\0\0II*\0\x08\0\0\0\x01\0".to_vec();
+    exif.extend_from_slice(&[0x12, 0x01, 3, 0, 1, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0]);
+    let mut bytes = jpeg.into_inner();
+    let segment = [
+        &[0xff, 0xe1],
+        &(exif.len() as u16 + 2).to_be_bytes()[..],
+        &exif,
+    ]
+    .concat();
+    bytes.splice(2..2, segment);
+    assert_eq!(6, rsc::Metadata::from_bytes(&bytes).unwrap().orientation());
+
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("strip_metadata");
+    std::fs::create_dir_all(&dir).unwrap();
+    let infile = dir.join("rotated.jpg");
+    std::fs::write(&infile, &bytes).unwrap();
+    let args = [
+        String::from("rsc"),
+        String::from("--strip-metadata"),
+        infile.display().to_string(),
+        String::from("50%"),
+        String::from("100%"),
+    ];
+    rsc::run(rsc::parser::Config::new(&args).unwrap()).unwrap();
+    let carved = std::fs::read(dir.join("rotated_carved.jpg")).unwrap();
+    assert_eq!(
+        (4, 12),
+        image::load_from_memory(&carved)
+            .unwrap()
+            .to_rgb8()
+            .dimensions()
+    );
+    assert_eq!(None, rsc::Metadata::from_bytes(&carved).unwrap().exif);
+}
+
+#[cfg(feature = "server")]
+#[test]
+fn server_localhost() {
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    let http = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let addr = http.server_addr().to_ip().unwrap();
+    let options = rsc::server::ServerOptions {
+        workers: 2,
+        max_body: 1 << 20,
+        ..Default::default()
+    };
+    std::thread::spawn(move || rsc::server::serve(&http, &options).unwrap());
+
+    // the announced length may exceed the body, to be rejected before anything is sent
+    let post = |query: &str, body: &[u8], length: usize| {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "POST /?{} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            query, length
+        )
+        .unwrap();
+        stream.write_all(body).unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+        let split = response.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        let status = String::from_utf8_lossy(&response[9..12]).into_owned();
+        (status, response[split + 4..].to_vec())
+    };
+
+    let mut body = std::io::Cursor::new(Vec::new());
+    image::RgbImage::from_fn(40, 30, |x, y| Rgb([(x * 6) as u8, (y * 8) as u8, 0]))
+        .write_to(&mut body, image::ImageFormat::Png)
+        .unwrap();
+    let body = body.into_inner();
+    let (status, carved) = post("width=50%25&height=20&format=bmp", &body, body.len());
+    assert_eq!("200", status);
+    let carved = image::load_from_memory(&carved).unwrap();
+    assert_eq!((20, 20), (carved.width(), carved.height()));
+
+    let (status, message) = post("width=0", &body, body.len());
+    assert_eq!("400", status);
+    assert_eq!(b"new_width must be positive, got 0".to_vec(), message);
+
+    let (status, _) = post("width=10", &[], (1 << 20) + 1);
+    assert_eq!("413", status);
+}
+
+// the test binary lives in target/<profile>/deps, next to the cdylib
+#[cfg(any(feature = "ffi", feature = "python"))]
+fn shared_lib() -> std::path::PathBuf {
+    let exe = std::env::current_exe().unwrap();
+    let deps = exe.parent().unwrap();
+    [deps, deps.parent().unwrap()]
+        .iter()
+        .flat_map(|dir| [dir.join("librsc.so"), dir.join("librsc.dylib")])
+        .find(|lib| lib.exists())
+        .expect("librsc shared library not found")
+}
+
+#[cfg(feature = "ffi")]
+#[test]
+fn ffi_c_smoke() {
+    use std::path::Path;
+    use std::process::Command;
+
+    let lib = shared_lib();
+    let lib_dir = lib.parent().unwrap();
+    let bin = Path::new(env!("CARGO_TARGET_TMPDIR")).join("ffi_smoke");
+    let status = Command::new("cc")
+        .args(["tests/ffi_smoke.c", "-Iinclude", "-lrsc", "-o"])
+        .arg(&bin)
+        .arg(format!("-L{}", lib_dir.display()))
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .status()
+        .unwrap();
+    assert!(status.success());
+    // cargo's library path may hold a stale build of the library, taking precedence over the rpath
+    let output = Command::new(&bin)
+        .env_remove("LD_LIBRARY_PATH")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[cfg(feature = "python")]
+#[test]
+fn python_bindings() {
+    use std::path::Path;
+    use std::process::Command;
+
+    // skipped where no interpreter with numpy is available
+    let python = std::env::var("PYTHON").unwrap_or_else(|_| String::from("python3"));
+    let numpy = Command::new(&python).args(["-c", "import numpy"]).output();
+    if !numpy.is_ok_and(|output| output.status.success()) {
+        eprintln!("skipping python_bindings, {} cannot import numpy", python);
+        return;
+    }
+    // python imports the extension module by its name, without the lib prefix
+    let module_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("python");
+    std::fs::create_dir_all(&module_dir).unwrap();
+    std::fs::copy(shared_lib(), module_dir.join("rsc.so")).unwrap();
+    let output = Command::new(&python)
+        .arg("tests/test_python.py")
+        .env("PYTHONPATH", &module_dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
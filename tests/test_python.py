@@ -0,0 +1,97 @@
+# exercises the python bindings, with the extension module built by `cargo build --features
+# python` and importable as `rsc` (e.g. librsc.so copied to rsc.so on the PYTHONPATH)
+import unittest
+
+import numpy as np
+
+import rsc
+
+
+def gradient_img(width, height, channels=3):
+    y, x = np.mgrid[0:height, 0:width].astype(np.uint32)
+    planes = [x * 37 % 256, y * 59 % 256, x * y % 256, (x + y) * 13 % 256]
+    return np.stack(planes[:channels], axis=-1).astype(np.uint8)
+
+
+def assert_connected(test, seam):
+    test.assertTrue(np.all(np.abs(np.diff(seam.astype(np.int64))) <= 1))
+
+
+class TestBindings(unittest.TestCase):
+    def test_seamcarve(self):
+        for channels in (3, 4):
+            img = gradient_img(12, 10, channels)
+            carved = rsc.seamcarve(img, 9, 8)
+            self.assertEqual((8, 9, channels), carved.shape)
+            self.assertEqual(np.uint8, carved.dtype)
+        carved = rsc.seamcarve(gradient_img(12, 10), 14, 10, seams_per_pass=2, order="horizontal-first")
+        self.assertEqual((10, 14, 3), carved.shape)
+
+    def test_energy(self):
+        img = gradient_img(7, 5)
+        energy = rsc.energy(img)
+        self.assertEqual((5, 7), energy.shape)
+        self.assertEqual(np.uint32, energy.dtype)
+        protect = np.zeros((5, 7), dtype=bool)
+        protect[2, 3] = True
+        protected = rsc.energy(img, protect=protect)
+        self.assertEqual(np.iinfo(np.uint32).max, protected[2, 3])
+        with self.assertRaises(ValueError):
+            rsc.energy(img, protect=np.zeros((4, 7), dtype=bool))
+
+    def test_find_seams(self):
+        energy = rsc.energy(gradient_img(9, 7))
+        vertical = rsc.find_vertical_seam(energy)
+        self.assertEqual(7, len(vertical))
+        self.assertTrue(np.all(vertical < 9))
+        assert_connected(self, vertical)
+        horizontal = rsc.find_horizontal_seam(energy)
+        self.assertEqual(9, len(horizontal))
+        self.assertTrue(np.all(horizontal < 7))
+        assert_connected(self, horizontal)
+        # the horizontal seam is the vertical one of the transposed energy
+        transposed = np.ascontiguousarray(energy.T)
+        np.testing.assert_array_equal(horizontal, rsc.find_vertical_seam(transposed))
+        seam = rsc.find_vertical_seam(energy, connectivity=2, tie_break="random", seed=7)
+        self.assertEqual(7, len(seam))
+
+    def test_no_copy(self):
+        # contiguous arrays are borrowed and the others copied, with the same results
+        img = gradient_img(12, 10)
+        strided = np.ascontiguousarray(np.flip(img, axis=1))[:, ::-1]
+        self.assertFalse(strided.flags["C_CONTIGUOUS"])
+        np.testing.assert_array_equal(img, strided)
+        np.testing.assert_array_equal(rsc.seamcarve(img, 9, 8), rsc.seamcarve(strided, 9, 8))
+        np.testing.assert_array_equal(rsc.energy(img), rsc.energy(strided))
+        energy = rsc.energy(img)
+        np.testing.assert_array_equal(
+            rsc.find_vertical_seam(energy), rsc.find_vertical_seam(np.asfortranarray(energy))
+        )
+        # the carved buffer is handed over to numpy, which owns it
+        carved = rsc.seamcarve(img, 9, 8)
+        self.assertTrue(carved.flags["C_CONTIGUOUS"])
+        self.assertTrue(carved.flags["WRITEABLE"])
+
+    def test_kwargs(self):
+        img = gradient_img(6, 5)
+        with self.assertRaises(TypeError):
+            rsc.seamcarve(img, 4, 5, scale=2)
+        with self.assertRaises(TypeError):
+            rsc.energy(img, seams_per_pass=2)
+        with self.assertRaises(TypeError):
+            rsc.find_vertical_seam(rsc.energy(img), order="vertical-first")
+        with self.assertRaises(ValueError):
+            rsc.seamcarve(img, 4, 5, tie_break="first")
+        with self.assertRaises(ValueError):
+            rsc.energy(img, energy="laplacian")
+
+    def test_invalid_arrays(self):
+        with self.assertRaises(ValueError):
+            rsc.seamcarve(gradient_img(6, 5)[:, :, :2], 4, 5)
+        # no memory is needed for an empty array that is too wide
+        with self.assertRaises(ValueError):
+            rsc.energy(np.zeros((0, 2**32, 3), dtype=np.uint8))
+
+
+if __name__ == "__main__":
+    unittest.main()